@@ -0,0 +1,108 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::{io::Write, path::PathBuf};
+
+use clap::{Parser, ValueHint};
+
+use crate::{
+    convert::{spdx_to_cyclonedx, spdx_to_spdx3, CycloneDxFormat},
+    utilities::{create_spdx_writer, deserialize_spdx, SpdxOptions},
+};
+
+/// Arguments for the convert subcommand.
+#[derive(Parser, Debug)]
+pub struct ConvertArguments {
+    /// Format to convert to.
+    #[clap(subcommand)]
+    action: ConvertAction,
+}
+
+/// Sub(sub)commands for the convert subcommand.
+#[derive(Parser, Debug)]
+enum ConvertAction {
+    /// Convert an SPDX 2 document to SPDX 3.0 JSON-LD.
+    Spdx3(Spdx3Arguments),
+
+    /// Convert an SPDX document to a CycloneDX 1.5 SBOM.
+    Cyclonedx(CycloneDxArguments),
+}
+
+pub fn convert_command(
+    arguments: ConvertArguments,
+    spdx_options: SpdxOptions,
+) -> anyhow::Result<()> {
+    match arguments.action {
+        // Process SPDX 3 subcommand of convert.
+        ConvertAction::Spdx3(arguments) => spdx3(arguments, spdx_options)?,
+
+        // Process CycloneDX subcommand of convert.
+        ConvertAction::Cyclonedx(arguments) => cyclonedx(arguments, spdx_options)?,
+    }
+
+    Ok(())
+}
+
+#[derive(Parser, Debug)]
+pub struct Spdx3Arguments {
+    /// Path to the SPDX document to convert, or `-` for stdin.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+
+    /// Path to output the SPDX 3.0 JSON-LD document to, or `-` for stdout.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    output: PathBuf,
+}
+
+pub fn spdx3(arguments: Spdx3Arguments, spdx_options: SpdxOptions) -> anyhow::Result<()> {
+    let spdx = deserialize_spdx(&arguments.input, spdx_options.format)?;
+
+    let document = spdx_to_spdx3(&spdx);
+
+    let mut writer = create_spdx_writer(&arguments.output)?;
+    if spdx_options.compact {
+        serde_json::to_writer(&mut writer, &document)?;
+    } else {
+        serde_json::to_writer_pretty(&mut writer, &document)?;
+    }
+    writer.finish()?;
+
+    Ok(())
+}
+
+#[derive(Parser, Debug)]
+pub struct CycloneDxArguments {
+    /// Path to the SPDX document to convert, or `-` for stdin.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+
+    /// Path to output the CycloneDX SBOM to, or `-` for stdout.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    output: PathBuf,
+
+    /// Format of the SBOM. Detected from the extension of the output if not given, defaulting
+    /// to JSON.
+    #[clap(short, long, value_enum)]
+    format: Option<CycloneDxFormat>,
+}
+
+pub fn cyclonedx(arguments: CycloneDxArguments, spdx_options: SpdxOptions) -> anyhow::Result<()> {
+    let spdx = deserialize_spdx(&arguments.input, spdx_options.format)?;
+
+    let bom = spdx_to_cyclonedx(&spdx);
+
+    let format = arguments
+        .format
+        .unwrap_or_else(|| CycloneDxFormat::from_path(&arguments.output));
+    let output = match format {
+        CycloneDxFormat::Json => bom.to_json(spdx_options.compact)?,
+        CycloneDxFormat::Xml => bom.to_xml(),
+    };
+
+    let mut writer = create_spdx_writer(&arguments.output)?;
+    writer.write_all(output.as_bytes())?;
+    writer.finish()?;
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::path::PathBuf;
+
+use clap::{Parser, ValueHint};
+
+use crate::{
+    export::{export_spdx, Column},
+    utilities::{deserialize_spdx, SpdxOptions},
+};
+
+/// Arguments for the export subcommand.
+#[derive(Parser, Debug)]
+pub struct ExportArguments {
+    /// Path to the SPDX document to export.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+
+    /// Path to output the spreadsheet to. The format is selected by the extension, either
+    /// `.csv` or `.xlsx`.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    output: PathBuf,
+
+    /// Columns to include in the spreadsheet, separated by commas. Includes all columns by
+    /// default.
+    #[clap(short, long, value_enum, value_delimiter = ',')]
+    columns: Vec<Column>,
+}
+
+pub fn export_command(arguments: ExportArguments, spdx_options: SpdxOptions) -> anyhow::Result<()> {
+    let spdx = deserialize_spdx(&arguments.input, spdx_options.format)?;
+
+    let columns = if arguments.columns.is_empty() {
+        Column::ALL.to_vec()
+    } else {
+        arguments.columns
+    };
+
+    export_spdx(&spdx, &arguments.output, &columns)?;
+
+    Ok(())
+}
@@ -2,15 +2,27 @@
 //
 // SPDX-License-Identifier: MIT
 
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use clap::{Parser, ValueHint};
+use anyhow::Context;
+use clap::{Args, Parser, ValueEnum, ValueHint};
 use fossology_rs::Fossology;
+use log::{error, info, warn};
 use spdx_toolkit::license_list::LicenseList;
 
 use crate::{
-    fossology::{populate_spdx_document_from_fossology, upload_missing_archives_to_fossology},
-    utilities::{deserialize_spdx, serialize_spdx},
+    fossology::{
+        populate_spdx_document_from_fossology, populate_spdx_stream_from_fossology, status_table,
+        upload_missing_archives_to_fossology, upload_statuses, wait_for_uploads, Agent,
+        AnalysisConfig, ArchiveMatching, Decider, ExclusionRules, FossologyRest, Polling,
+        Subfolders, UploadManifest, UploadOptions, UploadState, UploadStatus,
+    },
+    spdx::{validate_spdx, Severity},
+    utilities::{deserialize_spdx, serialize_spdx, SpdxFormat, SpdxOptions},
 };
 
 /// Arguments for the Fossology subcommand.
@@ -34,24 +46,36 @@ pub struct FossologyArguments {
 #[derive(Parser, Debug)]
 enum FossologyAction {
     /// Upload source archives to Fossology.
-    Upload(UploadArguments),
+    Upload(Box<UploadArguments>),
 
     /// Populate an SPDX file with license and copyritght information from Fossology.
     Query(QueryArguments),
+
+    /// Show the status of the jobs of the archives uploaded with the upload subcommand.
+    Status(StatusArguments),
 }
 
-pub fn fossology_command(arguments: FossologyArguments) -> anyhow::Result<()> {
+pub fn fossology_command(
+    arguments: FossologyArguments,
+    spdx_options: SpdxOptions,
+) -> anyhow::Result<()> {
     let fossology = Fossology::new(&arguments.uri, &arguments.token)?;
+    let fossology_rest = FossologyRest::new(&arguments.uri, &arguments.token)?;
 
     match arguments.action {
         // Process upload subcommand of Fossology.
         FossologyAction::Upload(arguments) => {
-            upload(arguments, &fossology)?;
+            upload(*arguments, &fossology, &fossology_rest, spdx_options)?;
         }
 
         // Process query subcommand of Fossology.
         FossologyAction::Query(arguments) => {
-            query(arguments, &fossology)?;
+            query(arguments, &fossology, spdx_options)?;
+        }
+
+        // Process status subcommand of Fossology.
+        FossologyAction::Status(arguments) => {
+            status(arguments, &fossology)?;
         }
     }
     Ok(())
@@ -63,31 +87,314 @@ pub struct UploadArguments {
     source_archive_paths: Vec<PathBuf>,
 
     /// ID of the folder in Fossology to upload the source to.
-    #[clap(short, long)]
-    folder: i32,
+    #[clap(short, long, required_unless_present = "folder-path")]
+    folder: Option<i32>,
+
+    /// Path of the folder in Fossology to upload the source to, separated with `/` and relative
+    /// to the root folder. Missing folders are created. Example: `Product/Release-2026.10`.
+    #[clap(long, conflicts_with = "folder")]
+    folder_path: Option<String>,
+
+    /// Organise the uploads into subfolders of the folder by the packages of the archives.
+    #[clap(long, value_enum, requires_if("layer", "layers"))]
+    subfolders: Option<SubfolderGrouping>,
 
-    /// Path to the SPDX Document to get the packages that should be skipped. Packages with
-    /// CLOSED in their declared license won't be uploaded.
+    /// Path to a YAML or JSON file mapping the names of the packages to their layers, used for
+    /// the layer subfolders.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    layers: Option<PathBuf>,
+
+    /// Path to the SPDX Document to get the packages that should be skipped.
     #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
     spdx: PathBuf,
 
+    /// Path to a YAML or JSON file with the rules for the packages whose sources are not uploaded.
+    /// Packages with CLOSED in their declared license are excluded by default.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    exclusion_rules: Option<PathBuf>,
+
+    /// File names of archives to upload even if they are the source of a package that should not
+    /// be uploaded.
+    #[clap(long, value_delimiter = ',')]
+    include_archives: Vec<String>,
+
+    /// File names of archives never to upload.
+    #[clap(long, value_delimiter = ',')]
+    exclude_archives: Vec<String>,
+
+    /// Path to a YAML or JSON file mapping the names of Yocto recipes to their `SRC_URI` entries,
+    /// used to match archives to the packages in addition to the SPDX document.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    src_uris: Option<PathBuf>,
+
     /// Don't actually upload the packages, but print packages that would be uploaded and
-    /// packages that would be skipped based on having a closed license.
+    /// packages that would be skipped based on the exclusion rules.
     #[clap(long)]
     dry_run: bool,
+
+    /// Maximum number of archives to upload to Fossology at the same time.
+    #[clap(long, default_value = "4")]
+    concurrent_uploads: usize,
+
+    /// Path to a YAML or JSON file with the agents, deciders and reuse settings to schedule for
+    /// the uploads. All agents and deciders are scheduled by default.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    analysis_config: Option<PathBuf>,
+
+    /// Agents to schedule, overriding the agents of the analysis config.
+    #[clap(long, value_enum, value_delimiter = ',')]
+    agents: Option<Vec<Agent>>,
+
+    /// Deciders to schedule, overriding the deciders of the analysis config.
+    #[clap(long, value_enum, value_delimiter = ',')]
+    deciders: Option<Vec<Decider>>,
+
+    /// ID of an earlier upload to reuse the conclusions of, overriding the analysis config.
+    #[clap(long)]
+    reuse_upload: Option<i32>,
+
+    /// Reuse the conclusions of the most recent earlier upload of the same package in the folder.
+    /// The package is matched by the name parsed from the file name of the archive.
+    #[clap(long)]
+    reuse_previous_version: bool,
+
+    /// Seconds to wait between checking if the uploads have been unarchived. Doubled after every
+    /// check up to `--max-poll-interval`.
+    #[clap(long, default_value = "10")]
+    poll_interval: u64,
+
+    /// Maximum seconds to wait between checking if the uploads have been unarchived.
+    #[clap(long, default_value = "300")]
+    max_poll_interval: u64,
+
+    /// Maximum seconds to wait for the uploads to be unarchived before giving up on them.
+    #[clap(long, default_value = "3600")]
+    unpack_timeout: u64,
+
+    /// Path to save the uploads made to, for checking the status of their jobs later. The uploads
+    /// are added to the uploads already in the file.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath, default_value = UPLOAD_STATE_FILE)]
+    state_file: PathBuf,
+
+    /// Path to write the manifest of the processed archives to. The manifest links the archives
+    /// to the packages in SPDX and to the uploads on Fossology, and can be given to the query
+    /// subcommand.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    manifest: Option<PathBuf>,
+
+    #[clap(flatten)]
+    wait: WaitArguments,
 }
 
-pub fn upload(arguments: UploadArguments, fossology: &Fossology) -> anyhow::Result<()> {
-    let spdx = deserialize_spdx(&arguments.spdx)?;
+/// Grouping of the uploads into subfolders.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum SubfolderGrouping {
+    /// Subfolder per supplier of the package.
+    Supplier,
+
+    /// Subfolder per layer of the package, read from the file given with `--layers`.
+    Layer,
+}
+
+/// Default path of the state file of the uploads.
+const UPLOAD_STATE_FILE: &str = "fossology-uploads.json";
+
+/// Arguments for waiting for the jobs of the uploads to finish.
+#[derive(Args, Debug)]
+pub struct WaitArguments {
+    /// Wait for the jobs of the uploads to finish and fail if any of them fail.
+    #[clap(long)]
+    wait: bool,
+
+    /// Maximum time in seconds to wait for the jobs to finish.
+    #[clap(long, default_value = "21600", requires = "wait")]
+    timeout: u64,
+
+    /// Seconds to wait between checking the status of the jobs. Doubled after every check up to
+    /// `--max-job-poll-interval`.
+    #[clap(long, default_value = "30", requires = "wait")]
+    job_poll_interval: u64,
+
+    /// Maximum seconds to wait between checking the status of the jobs.
+    #[clap(long, default_value = "300", requires = "wait")]
+    max_job_poll_interval: u64,
+}
+
+pub fn upload(
+    arguments: UploadArguments,
+    fossology: &Fossology,
+    fossology_rest: &FossologyRest,
+    spdx_options: SpdxOptions,
+) -> anyhow::Result<()> {
+    let spdx = deserialize_spdx(&arguments.spdx, spdx_options.format)?;
+
+    let mut analysis_config = match &arguments.analysis_config {
+        Some(path) => AnalysisConfig::from_path(path)?,
+        None => AnalysisConfig::default(),
+    };
+    if let Some(agents) = arguments.agents {
+        analysis_config.agents = agents;
+    }
+    if let Some(deciders) = arguments.deciders {
+        analysis_config.deciders = deciders;
+    }
+    if let Some(upload) = arguments.reuse_upload {
+        analysis_config.reuse.upload = Some(upload);
+    }
+    if arguments.reuse_previous_version {
+        analysis_config.reuse.previous_version = true;
+    }
+
+    let folder = match (arguments.folder, &arguments.folder_path) {
+        (Some(folder), _) => folder,
+        (None, Some(folder_path)) => {
+            // Don't create folders in a dry run, the folder isn't used for anything.
+            let mut folders = fossology_rest
+                .folders()
+                .context("Getting the folders from Fossology failed")?;
+            let folder = fossology_rest
+                .folder_path(&mut folders, None, folder_path, !arguments.dry_run)
+                .with_context(|| format!("Resolving folder {} failed", folder_path))?;
+            match folder {
+                Some(folder) => folder,
+                None => {
+                    info!("DRY_RUN: Real run would create folder {}.", folder_path);
+                    0
+                }
+            }
+        }
+        (None, None) => unreachable!("clap requires the folder or the folder path"),
+    };
+
+    let subfolders = match arguments.subfolders {
+        Some(SubfolderGrouping::Supplier) => Some(Subfolders::Supplier),
+        Some(SubfolderGrouping::Layer) => {
+            let layers = arguments
+                .layers
+                .as_ref()
+                .expect("clap requires the layers for the layer subfolders");
+            Some(Subfolders::Layer(Subfolders::read_layers(layers)?))
+        }
+        None => None,
+    };
+
+    let options = UploadOptions {
+        folder,
+        dry_run: arguments.dry_run,
+        concurrent_uploads: arguments.concurrent_uploads,
+        analysis: analysis_config,
+        unpack_polling: Polling {
+            interval: Duration::from_secs(arguments.poll_interval),
+            max_interval: Duration::from_secs(arguments.max_poll_interval),
+            timeout: Duration::from_secs(arguments.unpack_timeout),
+        },
+        exclusion_rules: exclusion_rules(&arguments.exclusion_rules)?,
+        subfolders,
+        matching: ArchiveMatching {
+            include: arguments.include_archives,
+            exclude: arguments.exclude_archives,
+            src_uris: match &arguments.src_uris {
+                Some(path) => ArchiveMatching::read_src_uris(path)?,
+                None => HashMap::new(),
+            },
+        },
+    };
 
-    upload_missing_archives_to_fossology(
+    let report = upload_missing_archives_to_fossology(
         arguments.source_archive_paths,
         fossology,
-        &arguments.folder,
+        fossology_rest,
         &spdx.package_information,
-        arguments.dry_run,
+        &options,
     )?;
 
+    if !arguments.dry_run {
+        // Keep tracking the uploads of the earlier runs.
+        if !report.uploads.is_empty() {
+            let mut tracked = if arguments.state_file.exists() {
+                UploadState::from_path(&arguments.state_file)?
+            } else {
+                UploadState::default()
+            };
+            tracked.merge(&report.uploads);
+            tracked.save(&arguments.state_file)?;
+        }
+
+        // Only wait for the uploads of this run.
+        let state = UploadState {
+            uploads: report.uploads,
+        };
+
+        if let Some(manifest) = &arguments.manifest {
+            report.manifest.save(manifest)?;
+        }
+
+        if arguments.wait.wait {
+            report_upload_statuses(fossology, &state, &arguments.wait)?;
+        }
+
+        if !report.failures.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{} archives could not be analysed on Fossology.",
+                report.failures.len()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Parser, Debug)]
+pub struct StatusArguments {
+    /// Path to the state file saved by the upload subcommand.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath, default_value = UPLOAD_STATE_FILE)]
+    state_file: PathBuf,
+
+    #[clap(flatten)]
+    wait: WaitArguments,
+}
+
+pub fn status(arguments: StatusArguments, fossology: &Fossology) -> anyhow::Result<()> {
+    let state = UploadState::from_path(&arguments.state_file)?;
+
+    report_upload_statuses(fossology, &state, &arguments.wait)
+}
+
+/// Print the statuses of the uploads, waiting for the jobs to finish if requested. Fails if any
+/// of the jobs failed or didn't finish in time when waiting.
+fn report_upload_statuses(
+    fossology: &Fossology,
+    state: &UploadState,
+    arguments: &WaitArguments,
+) -> anyhow::Result<()> {
+    let statuses = if arguments.wait {
+        let polling = Polling {
+            interval: Duration::from_secs(arguments.job_poll_interval),
+            max_interval: Duration::from_secs(arguments.max_job_poll_interval),
+            timeout: Duration::from_secs(arguments.timeout),
+        };
+        wait_for_uploads(fossology, state, &polling)?
+    } else {
+        upload_statuses(fossology, state)?
+    };
+
+    print!("{}", status_table(&statuses));
+
+    if arguments.wait {
+        let unfinished = statuses
+            .iter()
+            .filter(|(_, status)| status != &UploadStatus::Completed)
+            .count();
+
+        if unfinished > 0 {
+            return Err(anyhow::anyhow!(
+                "Jobs of {} of {} uploads failed or didn't finish in time.",
+                unfinished,
+                statuses.len()
+            ));
+        }
+    }
+
     Ok(())
 }
 
@@ -100,15 +407,112 @@ pub struct QueryArguments {
     /// Path to output the populated SPDX document to.
     #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
     output: PathBuf,
-}
 
-pub fn query(arguments: QueryArguments, fossology: &Fossology) -> anyhow::Result<()> {
-    let mut spdx = deserialize_spdx(&arguments.input)?;
+    /// Validate the populated SPDX document and don't write it if validation finds errors.
+    #[clap(long, conflicts_with = "stream")]
+    validate: bool,
+
+    /// Process the files of the SPDX document one at a time to reduce the memory usage for huge
+    /// documents. Only the files are streamed, the relationships and the rest of the document are
+    /// kept in memory. Without streaming, the whole input is read into memory before parsing.
+    /// Requires a JSON input file, which is read twice.
+    #[clap(long)]
+    stream: bool,
 
+    /// Path to the manifest written by the upload subcommand. Adds the archives and the uploads
+    /// of the packages to the SPDX document, and warns about packages that were not scanned or
+    /// whose uploads have not been analysed. The findings of the files are still looked up by
+    /// their hashes.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    manifest: Option<PathBuf>,
+
+    /// Path to a YAML or JSON file with the rules for the packages whose sources are not queried.
+    /// Packages with CLOSED in their declared license are excluded by default.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    exclusion_rules: Option<PathBuf>,
+}
+
+pub fn query(
+    arguments: QueryArguments,
+    fossology: &Fossology,
+    spdx_options: SpdxOptions,
+) -> anyhow::Result<()> {
     let license_list = LicenseList::from_github(Some("v3.17"))?;
-    populate_spdx_document_from_fossology(fossology, &mut spdx, &license_list)?;
+    let manifest = arguments
+        .manifest
+        .as_ref()
+        .map(UploadManifest::from_path)
+        .transpose()?;
+    let exclusion_rules = exclusion_rules(&arguments.exclusion_rules)?;
+
+    if arguments.stream {
+        let is_json = |path: &PathBuf| {
+            spdx_options
+                .format
+                .or_else(|| SpdxFormat::from_path(path).ok())
+                == Some(SpdxFormat::Json)
+        };
 
-    serialize_spdx(arguments.output, &spdx)?;
+        if arguments.input == Path::new("-")
+            || !is_json(&arguments.input)
+            || !is_json(&arguments.output)
+        {
+            return Err(anyhow::anyhow!(
+                "streaming requires a JSON input file and JSON output"
+            ));
+        }
+
+        return populate_spdx_stream_from_fossology(
+            fossology,
+            &arguments.input,
+            &arguments.output,
+            &license_list,
+            spdx_options.compact,
+            manifest.as_ref(),
+            &exclusion_rules,
+        );
+    }
+
+    let mut spdx = deserialize_spdx(&arguments.input, spdx_options.format)?;
+    populate_spdx_document_from_fossology(
+        fossology,
+        &mut spdx,
+        &license_list,
+        manifest.as_ref(),
+        &exclusion_rules,
+    )?;
+
+    if arguments.validate {
+        let mut errors = 0;
+
+        for diagnostic in validate_spdx(&spdx, &license_list) {
+            match diagnostic.severity {
+                Severity::Error => {
+                    error!("{}", diagnostic);
+                    errors += 1;
+                }
+                Severity::Warning => warn!("{}", diagnostic),
+            }
+        }
+
+        if errors > 0 {
+            return Err(anyhow::anyhow!(
+                "populated SPDX document has {} validation errors",
+                errors
+            ));
+        }
+    }
+
+    serialize_spdx(arguments.output, &spdx, spdx_options)?;
 
     Ok(())
 }
+
+/// Read the exclusion rules from the path, or use the default rules.
+fn exclusion_rules(path: &Option<PathBuf>) -> anyhow::Result<ExclusionRules> {
+    Ok(path
+        .as_ref()
+        .map(ExclusionRules::from_path)
+        .transpose()?
+        .unwrap_or_default())
+}
@@ -0,0 +1,88 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::{
+    fmt::Write,
+    io::stdin,
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, ValueHint};
+
+use crate::{
+    spdx::algorithm_name,
+    utilities::{hashes_for_path, FileHashes},
+};
+
+/// Arguments for the hash subcommand.
+#[derive(Parser, Debug)]
+pub struct HashArguments {
+    /// Paths to the files to hash, or `-` for stdin.
+    #[clap(required = true, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    files: Vec<PathBuf>,
+}
+
+pub fn hash_command(arguments: HashArguments) -> anyhow::Result<()> {
+    let mut output = String::new();
+
+    for (index, path) in arguments.files.iter().enumerate() {
+        let hashes = if path == Path::new("-") {
+            FileHashes::from_reader(stdin().lock())?
+        } else {
+            hashes_for_path(path)?
+        };
+
+        if index > 0 {
+            writeln!(output)?;
+        }
+        write_checksums(&mut output, path, &hashes)?;
+    }
+
+    print!("{}", output);
+
+    Ok(())
+}
+
+/// Write the checksums of the file like in SPDX tag-value documents.
+fn write_checksums(output: &mut String, path: &Path, hashes: &FileHashes) -> std::fmt::Result {
+    writeln!(output, "FileName: {}", path.display())?;
+    for checksum in hashes.checksums() {
+        writeln!(
+            output,
+            "FileChecksum: {}: {}",
+            algorithm_name(checksum.algorithm),
+            checksum.value
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_are_written_as_tag_value() {
+        let mut output = String::new();
+        write_checksums(
+            &mut output,
+            Path::new("./empty"),
+            &FileHashes::from_reader(std::io::empty()).unwrap(),
+        )
+        .unwrap();
+
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "FileName: ./empty");
+        assert_eq!(
+            lines[1],
+            "FileChecksum: SHA1: da39a3ee5e6b4b0d3255bfef95601890afd80709"
+        );
+        assert!(lines[3].starts_with("FileChecksum: SHA512: cf83e1357eefb8bd"));
+        assert_eq!(
+            lines[4],
+            "FileChecksum: MD5: d41d8cd98f00b204e9800998ecf8427e"
+        );
+    }
+}
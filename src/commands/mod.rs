@@ -2,11 +2,27 @@
 //
 // SPDX-License-Identifier: MIT
 
+mod convert;
+mod export;
 mod fossology;
+mod hash;
+mod report;
+mod scan;
+mod spdx;
 
 use clap::Parser;
 
-use self::fossology::{fossology_command, FossologyArguments};
+use crate::utilities::SpdxOptions;
+
+use self::{
+    convert::{convert_command, ConvertArguments},
+    export::{export_command, ExportArguments},
+    fossology::{fossology_command, FossologyArguments},
+    hash::{hash_command, HashArguments},
+    report::{report_command, ReportArguments},
+    scan::{scan_command, ScanArguments},
+    spdx::{spdx_command, SpdxArguments},
+};
 
 /// Command line options.
 #[derive(Parser, Debug)]
@@ -15,6 +31,9 @@ pub struct Opts {
     /// Subcommand to run.
     #[clap(subcommand)]
     subcmd: SubCommand,
+
+    #[clap(flatten)]
+    spdx_options: SpdxOptions,
 }
 
 /// Interact with Fossology and process SPDX document with the data.
@@ -23,14 +42,112 @@ enum SubCommand {
     /// Interact with Fossology.
     #[clap(author, version)]
     Fossology(FossologyArguments),
+
+    /// Create reports from SPDX documents.
+    #[clap(author, version)]
+    Report(ReportArguments),
+
+    /// Export package and file findings of an SPDX document to CSV or XLSX.
+    #[clap(author, version)]
+    Export(ExportArguments),
+
+    /// Process SPDX documents.
+    #[clap(author, version)]
+    Spdx(SpdxArguments),
+
+    /// Convert SPDX documents to other formats.
+    #[clap(author, version)]
+    Convert(ConvertArguments),
+
+    /// Scan local sources for checksums, license identifiers and copyrights to populate an SPDX
+    /// document without Fossology.
+    #[clap(author, version)]
+    Scan(ScanArguments),
+
+    /// Print the SPDX checksums of files.
+    #[clap(author, version)]
+    Hash(HashArguments),
 }
 
 pub fn main_command(opts: Opts) -> anyhow::Result<()> {
     // Process subcommands.
     match opts.subcmd {
         // Process Fossology subcommand.
-        SubCommand::Fossology(arguments) => fossology_command(arguments)?,
+        SubCommand::Fossology(arguments) => fossology_command(arguments, opts.spdx_options)?,
+
+        // Process report subcommand.
+        SubCommand::Report(arguments) => report_command(arguments, opts.spdx_options)?,
+
+        // Process export subcommand.
+        SubCommand::Export(arguments) => export_command(arguments, opts.spdx_options)?,
+
+        // Process SPDX subcommand.
+        SubCommand::Spdx(arguments) => spdx_command(arguments, opts.spdx_options)?,
+
+        // Process convert subcommand.
+        SubCommand::Convert(arguments) => convert_command(arguments, opts.spdx_options)?,
+
+        // Process scan subcommand.
+        SubCommand::Scan(arguments) => scan_command(arguments, opts.spdx_options)?,
+
+        // Process hash subcommand.
+        SubCommand::Hash(arguments) => hash_command(arguments)?,
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn global_spdx_options_dont_clash_with_subcommand_arguments() {
+        let opts = Opts::try_parse_from([
+            "doubleopen_cli",
+            "--spdx-format",
+            "yaml",
+            "spdx",
+            "diff",
+            "old.yaml",
+            "new.yaml",
+            "--format",
+            "json",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            opts.spdx_options.format,
+            Some(crate::utilities::SpdxFormat::Yaml)
+        );
+    }
+
+    #[test]
+    fn layer_subfolders_require_layers() {
+        let arguments = |extra: &[&'static str]| {
+            [
+                "doubleopen_cli",
+                "fossology",
+                "--uri",
+                "http://localhost/repo/api/v1",
+                "--token",
+                "token",
+                "upload",
+                "archive.tar.gz",
+                "--spdx",
+                "product.spdx.json",
+                "--folder",
+                "1",
+                "--subfolders",
+                "layer",
+            ]
+            .iter()
+            .chain(extra)
+            .copied()
+            .collect::<Vec<_>>()
+        };
+
+        assert!(Opts::try_parse_from(arguments(&[])).is_err());
+        Opts::try_parse_from(arguments(&["--layers", "layers.yaml"])).unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::{collections::HashMap, fs::write, path::PathBuf};
+
+use clap::{Parser, ValueHint};
+
+use crate::{
+    fossology::{ArchiveMatching, ExclusionRules},
+    reports::{html_report, source_offer_report},
+    utilities::{deserialize_spdx, SpdxOptions},
+};
+
+/// Arguments for the report subcommand.
+#[derive(Parser, Debug)]
+pub struct ReportArguments {
+    /// Report to create.
+    #[clap(subcommand)]
+    action: ReportAction,
+}
+
+/// Sub(sub)commands for the report subcommand.
+#[derive(Parser, Debug)]
+enum ReportAction {
+    /// List packages with source distribution obligations and their source archives.
+    SourceOffer(SourceOfferArguments),
+
+    /// Create a static HTML report for reviewing the licenses of a populated SPDX document.
+    Html(HtmlArguments),
+}
+
+pub fn report_command(arguments: ReportArguments, spdx_options: SpdxOptions) -> anyhow::Result<()> {
+    match arguments.action {
+        // Process source offer subcommand of report.
+        ReportAction::SourceOffer(arguments) => source_offer(arguments, spdx_options)?,
+
+        // Process HTML subcommand of report.
+        ReportAction::Html(arguments) => html(arguments, spdx_options)?,
+    }
+
+    Ok(())
+}
+
+#[derive(Parser, Debug)]
+pub struct SourceOfferArguments {
+    /// Source archives to match to the packages. Use pattern matching to include multiple files.
+    source_archive_paths: Vec<PathBuf>,
+
+    /// Path to the populated SPDX document.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    spdx: PathBuf,
+
+    /// Path to output the JSON report to.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    output: PathBuf,
+
+    /// Path to a YAML or JSON file mapping the names of Yocto recipes to their `SRC_URI` entries,
+    /// used to match archives to the packages in addition to the SPDX document.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    src_uris: Option<PathBuf>,
+}
+
+pub fn source_offer(
+    arguments: SourceOfferArguments,
+    spdx_options: SpdxOptions,
+) -> anyhow::Result<()> {
+    let spdx = deserialize_spdx(&arguments.spdx, spdx_options.format)?;
+
+    let matching = ArchiveMatching {
+        src_uris: match &arguments.src_uris {
+            Some(path) => ArchiveMatching::read_src_uris(path)?,
+            None => HashMap::new(),
+        },
+        ..Default::default()
+    };
+
+    let report = source_offer_report(&spdx, &arguments.source_archive_paths, &matching);
+
+    write(&arguments.output, serde_json::to_string_pretty(&report)?)?;
+
+    Ok(())
+}
+
+#[derive(Parser, Debug)]
+pub struct HtmlArguments {
+    /// Path to the populated SPDX document.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    spdx: PathBuf,
+
+    /// Path to output the HTML report to.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    output: PathBuf,
+
+    /// Path to a YAML or JSON file with the rules for the packages listed as excluded. Packages
+    /// with CLOSED in their declared license are excluded by default.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    exclusion_rules: Option<PathBuf>,
+}
+
+pub fn html(arguments: HtmlArguments, spdx_options: SpdxOptions) -> anyhow::Result<()> {
+    let spdx = deserialize_spdx(&arguments.spdx, spdx_options.format)?;
+
+    let exclusion_rules = arguments
+        .exclusion_rules
+        .as_ref()
+        .map(ExclusionRules::from_path)
+        .transpose()?
+        .unwrap_or_default();
+
+    write(&arguments.output, html_report(&spdx, &exclusion_rules)?)?;
+
+    Ok(())
+}
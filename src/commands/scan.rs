@@ -0,0 +1,45 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::path::PathBuf;
+
+use clap::{Parser, ValueHint};
+use spdx_toolkit::license_list::LicenseList;
+
+use crate::{
+    scanners::{populate_spdx_from_local_scan, scan_directory},
+    utilities::{deserialize_spdx, serialize_spdx, SpdxOptions},
+};
+
+/// Arguments for the scan subcommand.
+#[derive(Parser, Debug)]
+pub struct ScanArguments {
+    /// Path to the directory of unpacked sources to scan.
+    #[clap(parse(from_os_str), value_hint = ValueHint::DirPath)]
+    source_path: PathBuf,
+
+    /// Path to the SPDX document to populate.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+
+    /// Path to output the populated SPDX document to.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    output: PathBuf,
+
+    /// Version of the SPDX license list to check the detected licenses against.
+    #[clap(long, default_value = "v3.17")]
+    license_list_version: String,
+}
+
+pub fn scan_command(arguments: ScanArguments, spdx_options: SpdxOptions) -> anyhow::Result<()> {
+    let mut spdx = deserialize_spdx(&arguments.input, spdx_options.format)?;
+    let license_list = LicenseList::from_github(Some(&arguments.license_list_version))?;
+
+    let results = scan_directory(&arguments.source_path)?;
+    populate_spdx_from_local_scan(&mut spdx, &results, &license_list)?;
+
+    serialize_spdx(&arguments.output, &spdx, spdx_options)?;
+
+    Ok(())
+}
@@ -0,0 +1,269 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::{
+    fs::{create_dir_all, write, File},
+    io::BufReader,
+    path::PathBuf,
+};
+
+use clap::{Parser, ValueEnum, ValueHint};
+use spdx_rs::models::{Algorithm, Checksum};
+use spdx_toolkit::license_list::LicenseList;
+
+use crate::{
+    scanners::{populate_spdx_from_scancode, ScanCodeOutput},
+    spdx::{
+        diff_spdx, merge_spdx, package_document_file_names, split_spdx, validate_spdx, Severity,
+    },
+    utilities::{
+        deserialize_spdx, deserialize_spdx_with_sha1, serialize_spdx, SpdxFormat, SpdxOptions,
+    },
+};
+
+/// Arguments for the SPDX subcommand.
+#[derive(Parser, Debug)]
+pub struct SpdxArguments {
+    /// Action to do with the SPDX documents.
+    #[clap(subcommand)]
+    action: SpdxAction,
+}
+
+/// Sub(sub)commands for the SPDX subcommand.
+#[derive(Parser, Debug)]
+enum SpdxAction {
+    /// Compare two SPDX documents for license changes between releases.
+    Diff(DiffArguments),
+
+    /// Merge multiple SPDX documents into one.
+    Merge(MergeArguments),
+
+    /// Split an SPDX document to one document per package.
+    Split(SplitArguments),
+
+    /// Validate the integrity of an SPDX document.
+    Validate(ValidateArguments),
+
+    /// Populate the files of an SPDX document from the JSON output of ScanCode.
+    Scancode(ScancodeArguments),
+}
+
+pub fn spdx_command(arguments: SpdxArguments, spdx_options: SpdxOptions) -> anyhow::Result<()> {
+    match arguments.action {
+        // Process diff subcommand of SPDX.
+        SpdxAction::Diff(arguments) => diff(arguments, spdx_options)?,
+
+        // Process merge subcommand of SPDX.
+        SpdxAction::Merge(arguments) => merge(arguments, spdx_options)?,
+
+        // Process split subcommand of SPDX.
+        SpdxAction::Split(arguments) => split(arguments, spdx_options)?,
+
+        // Process validate subcommand of SPDX.
+        SpdxAction::Validate(arguments) => validate(arguments, spdx_options)?,
+
+        // Process ScanCode subcommand of SPDX.
+        SpdxAction::Scancode(arguments) => scancode(arguments, spdx_options)?,
+    }
+
+    Ok(())
+}
+
+/// Output format of the diff and validation results.
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Parser, Debug)]
+pub struct DiffArguments {
+    /// Path to the SPDX document of the previous release.
+    #[clap(parse(from_os_str), value_hint = ValueHint::FilePath)]
+    old: PathBuf,
+
+    /// Path to the SPDX document of the new release.
+    #[clap(parse(from_os_str), value_hint = ValueHint::FilePath)]
+    new: PathBuf,
+
+    /// Output format of the diff.
+    #[clap(short, long, value_enum, default_value = "text")]
+    format: OutputFormat,
+
+    /// Path to output the diff to. Printed to stdout if not given.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    output: Option<PathBuf>,
+}
+
+pub fn diff(arguments: DiffArguments, spdx_options: SpdxOptions) -> anyhow::Result<()> {
+    let old = deserialize_spdx(&arguments.old, spdx_options.format)?;
+    let new = deserialize_spdx(&arguments.new, spdx_options.format)?;
+
+    let diff = diff_spdx(&old, &new);
+
+    let output = match arguments.format {
+        OutputFormat::Text => diff.to_string(),
+        OutputFormat::Json => serde_json::to_string_pretty(&diff)?,
+    };
+
+    match arguments.output {
+        Some(path) => write(path, output)?,
+        None => print!("{}", output),
+    }
+
+    Ok(())
+}
+
+#[derive(Parser, Debug)]
+pub struct MergeArguments {
+    /// Paths to the SPDX documents to merge.
+    #[clap(required = true, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    inputs: Vec<PathBuf>,
+
+    /// Name of the merged SPDX document.
+    #[clap(short, long)]
+    name: String,
+
+    /// Path to output the merged SPDX document to.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    output: PathBuf,
+}
+
+pub fn merge(arguments: MergeArguments, spdx_options: SpdxOptions) -> anyhow::Result<()> {
+    let documents = arguments
+        .inputs
+        .iter()
+        .map(|input| deserialize_spdx(input, spdx_options.format))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let merged = merge_spdx(documents, &arguments.name);
+
+    serialize_spdx(arguments.output, &merged, spdx_options)?;
+
+    Ok(())
+}
+
+#[derive(Parser, Debug)]
+pub struct SplitArguments {
+    /// Path to the product SPDX document to split, or `-` for stdin.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+
+    /// Directory to output the SPDX documents of the packages to.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::DirPath)]
+    output_dir: PathBuf,
+}
+
+pub fn split(arguments: SplitArguments, spdx_options: SpdxOptions) -> anyhow::Result<()> {
+    let (product, sha1) = deserialize_spdx_with_sha1(&arguments.input, spdx_options.format)?;
+    let checksum = Checksum::new(Algorithm::SHA1, &sha1);
+
+    let output_format = spdx_options.format.unwrap_or(SpdxFormat::Json);
+
+    create_dir_all(&arguments.output_dir)?;
+
+    let documents = split_spdx(&product, &checksum);
+
+    for (spdx, name) in documents
+        .iter()
+        .zip(package_document_file_names(&documents))
+    {
+        let file_name = format!("{}.{}", name, output_format.extension());
+        serialize_spdx(
+            arguments.output_dir.join(file_name),
+            spdx,
+            SpdxOptions {
+                format: Some(output_format),
+                ..spdx_options
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Parser, Debug)]
+pub struct ValidateArguments {
+    /// Path to the SPDX document to validate.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+
+    /// Version of the SPDX license list to validate the license expressions against.
+    #[clap(long, default_value = "v3.17")]
+    license_list_version: String,
+
+    /// Output format of the diagnostics.
+    #[clap(short, long, value_enum, default_value = "text")]
+    format: OutputFormat,
+
+    /// Path to output the diagnostics to. Printed to stdout if not given.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    output: Option<PathBuf>,
+}
+
+pub fn validate(arguments: ValidateArguments, spdx_options: SpdxOptions) -> anyhow::Result<()> {
+    let spdx = deserialize_spdx(&arguments.input, spdx_options.format)?;
+    let license_list = LicenseList::from_github(Some(&arguments.license_list_version))?;
+
+    let diagnostics = validate_spdx(&spdx, &license_list);
+
+    let output = match arguments.format {
+        OutputFormat::Text => diagnostics
+            .iter()
+            .map(|diagnostic| format!("{}\n", diagnostic))
+            .collect(),
+        OutputFormat::Json => serde_json::to_string_pretty(&diagnostics)?,
+    };
+
+    match arguments.output {
+        Some(path) => write(path, output)?,
+        None => print!("{}", output),
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+
+    if errors > 0 {
+        Err(anyhow::anyhow!(
+            "SPDX document has {} validation errors",
+            errors
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+pub struct ScancodeArguments {
+    /// Path to the SPDX document to populate.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+
+    /// Path to the JSON output of ScanCode.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    scancode: PathBuf,
+
+    /// Path to output the populated SPDX document to.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    output: PathBuf,
+
+    /// Version of the SPDX license list to check the detected licenses against.
+    #[clap(long, default_value = "v3.17")]
+    license_list_version: String,
+}
+
+pub fn scancode(arguments: ScancodeArguments, spdx_options: SpdxOptions) -> anyhow::Result<()> {
+    let mut spdx = deserialize_spdx(&arguments.input, spdx_options.format)?;
+    let scancode: ScanCodeOutput =
+        serde_json::from_reader(BufReader::new(File::open(&arguments.scancode)?))?;
+    let license_list = LicenseList::from_github(Some(&arguments.license_list_version))?;
+
+    populate_spdx_from_scancode(&mut spdx, &scancode, &license_list)?;
+
+    serialize_spdx(&arguments.output, &spdx, spdx_options)?;
+
+    Ok(())
+}
@@ -0,0 +1,874 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Conversion of SPDX documents to CycloneDX 1.5 JSON and XML.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    path::Path,
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use spdx_rs::models::{
+    Algorithm, Checksum, FileInformation, PackageInformation, RelationshipType, SPDX,
+};
+
+use crate::{reports::escape_html, utilities::files_by_package};
+
+const SPEC_VERSION: &str = "1.5";
+const XML_NAMESPACE: &str = "http://cyclonedx.org/schema/bom/1.5";
+
+/// Output format of the CycloneDX SBOM.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CycloneDxFormat {
+    Json,
+    Xml,
+}
+
+impl CycloneDxFormat {
+    /// Detect the format from the extension of the path, defaulting to JSON.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("xml") => Self::Xml,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// CycloneDX bill of materials.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Bom {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    serial_number: String,
+    version: u32,
+    metadata: Metadata,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    components: Vec<Component>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    dependencies: Vec<Dependency>,
+}
+
+#[derive(Serialize, Debug)]
+struct Metadata {
+    timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Tools>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    authors: Vec<OrganizationalEntity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    component: Option<Component>,
+}
+
+#[derive(Serialize, Debug)]
+struct Tools {
+    components: Vec<Component>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct OrganizationalEntity {
+    name: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Component {
+    #[serde(rename = "type")]
+    component_type: &'static str,
+    #[serde(rename = "bom-ref", skip_serializing_if = "Option::is_none")]
+    bom_ref: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    supplier: Option<OrganizationalEntity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hashes: Vec<Hash>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    licenses: Vec<LicenseChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    copyright: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpe: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    purl: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    external_references: Vec<ExternalReference>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    components: Vec<Component>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    evidence: Option<Evidence>,
+}
+
+impl Component {
+    fn new(component_type: &'static str, name: &str) -> Self {
+        Self {
+            component_type,
+            bom_ref: None,
+            supplier: None,
+            author: None,
+            name: name.to_string(),
+            version: None,
+            description: None,
+            hashes: Vec::new(),
+            licenses: Vec::new(),
+            copyright: None,
+            cpe: None,
+            purl: None,
+            external_references: Vec::new(),
+            components: Vec::new(),
+            evidence: None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct Hash {
+    alg: &'static str,
+    content: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+enum LicenseChoice {
+    License { license: License },
+    Expression { expression: String },
+}
+
+#[derive(Serialize, Debug)]
+struct License {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<AttachedText>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct AttachedText {
+    content_type: &'static str,
+    content: String,
+}
+
+#[derive(Serialize, Debug)]
+struct ExternalReference {
+    #[serde(rename = "type")]
+    reference_type: &'static str,
+    url: String,
+}
+
+/// Evidence of the licenses and copyrights found by scanners.
+#[derive(Serialize, Debug)]
+struct Evidence {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    licenses: Vec<LicenseChoice>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    copyright: Vec<Copyright>,
+}
+
+#[derive(Serialize, Debug)]
+struct Copyright {
+    text: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Dependency {
+    #[serde(rename = "ref")]
+    dependency_ref: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<String>,
+}
+
+/// Convert an SPDX document to a CycloneDX SBOM.
+///
+/// Files are added as components of the package that contains them, and files not contained in
+/// any package as top level components. The package described by the document is added as the
+/// component of the metadata. License and copyright findings of files are added as evidence.
+pub fn spdx_to_cyclonedx(spdx: &SPDX) -> Bom {
+    let document_information = &spdx.document_creation_information;
+    let extracted_texts = spdx
+        .other_licensing_information_detected
+        .iter()
+        .map(|license| {
+            (
+                license.license_identifier.as_str(),
+                license.extracted_text.as_str(),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    let files_by_package = files_by_package(spdx);
+    let mut added_files = HashSet::new();
+
+    let mut packages = Vec::new();
+    for package in &spdx.package_information {
+        let mut component = package_component(package);
+
+        // A file can only be added to the SBOM once.
+        for file in files_by_package
+            .get(package.package_spdx_identifier.as_str())
+            .into_iter()
+            .flatten()
+        {
+            if added_files.insert(file.file_spdx_identifier.as_str()) {
+                component
+                    .components
+                    .push(file_component(file, &extracted_texts));
+            }
+        }
+
+        packages.push(component);
+    }
+
+    let mut components = Vec::new();
+    let mut metadata_component = None;
+    let described = described_elements(spdx);
+
+    for component in packages {
+        let is_root = described.len() == 1 && component.bom_ref.as_ref() == Some(&described[0]);
+        if is_root {
+            // The files of the described package are listed as top level components.
+            let mut component = component;
+            components.append(&mut component.components);
+            metadata_component = Some(component);
+        } else {
+            components.push(component);
+        }
+    }
+
+    for file in &spdx.file_information {
+        if added_files.insert(file.file_spdx_identifier.as_str()) {
+            components.push(file_component(file, &extracted_texts));
+        }
+    }
+
+    let mut tools = Vec::new();
+    let mut authors = Vec::new();
+    for creator in &document_information.creation_info.creators {
+        match creator.split_once(':') {
+            Some(("Tool", name)) => tools.push(Component::new("application", name.trim())),
+            Some((_, name)) => authors.push(OrganizationalEntity {
+                name: entity_name(name),
+            }),
+            None => authors.push(OrganizationalEntity {
+                name: entity_name(creator),
+            }),
+        }
+    }
+
+    Bom {
+        bom_format: "CycloneDX",
+        spec_version: SPEC_VERSION,
+        serial_number: serial_number(&document_information.spdx_document_namespace),
+        version: 1,
+        metadata: Metadata {
+            timestamp: document_information
+                .creation_info
+                .created
+                .format("%Y-%m-%dT%H:%M:%SZ")
+                .to_string(),
+            tools: if tools.is_empty() {
+                None
+            } else {
+                Some(Tools { components: tools })
+            },
+            authors,
+            component: metadata_component,
+        },
+        components,
+        dependencies: dependencies(spdx),
+    }
+}
+
+/// Get the SPDX IDs of the packages described by the document.
+fn described_elements(spdx: &SPDX) -> Vec<String> {
+    let document_information = &spdx.document_creation_information;
+    let mut described = document_information.document_describes.clone();
+
+    for relationship in &spdx.relationships {
+        if relationship.relationship_type == RelationshipType::Describes
+            && relationship.spdx_element_id == document_information.spdx_identifier
+            && !described.contains(&relationship.related_spdx_element)
+        {
+            described.push(relationship.related_spdx_element.clone());
+        }
+    }
+
+    described.retain(|id| {
+        spdx.package_information
+            .iter()
+            .any(|package| &package.package_spdx_identifier == id)
+    });
+
+    described
+}
+
+fn package_component(package: &PackageInformation) -> Component {
+    let component_type = package
+        .external_reference
+        .iter()
+        .find(|reference| reference.reference_type == "SPDX-2.3:primaryPackagePurpose")
+        .map(|reference| component_type(&reference.reference_locator))
+        .unwrap_or("library");
+
+    let mut component = Component::new(component_type, &package.package_name);
+    component.bom_ref = Some(package.package_spdx_identifier.clone());
+    component.supplier = package
+        .package_supplier
+        .as_deref()
+        .and_then(assertion)
+        .map(|supplier| OrganizationalEntity {
+            name: entity_name(supplier.split_once(':').map_or(supplier, |(_, name)| name)),
+        });
+    component.author = package
+        .package_originator
+        .as_deref()
+        .and_then(assertion)
+        .map(|originator| {
+            entity_name(
+                originator
+                    .split_once(':')
+                    .map_or(originator, |(_, name)| name),
+            )
+        });
+    component.version = package.package_version.clone();
+    component.description = package
+        .package_summary_description
+        .clone()
+        .or_else(|| package.package_detailed_description.clone());
+    component.hashes = hashes(&package.package_checksum);
+
+    // Use the concluded license of the package, or the declared license if there's no
+    // conclusion.
+    component.licenses = [&package.concluded_license, &package.declared_license]
+        .iter()
+        .map(|expression| expression.to_string())
+        .find(|expression| assertion(expression).is_some())
+        .map(|expression| vec![LicenseChoice::Expression { expression }])
+        .unwrap_or_default();
+    component.copyright = assertion(&package.copyright_text).map(ToString::to_string);
+
+    for reference in &package.external_reference {
+        let reference_type = reference
+            .reference_type
+            .trim_start_matches("http://spdx.org/rdf/references/");
+
+        match reference_type {
+            "purl" => component.purl = Some(reference.reference_locator.clone()),
+            "cpe22Type" | "cpe23Type" => component.cpe = Some(reference.reference_locator.clone()),
+            _ => {}
+        }
+    }
+
+    if let Some(url) = package.package_home_page.as_deref().and_then(assertion) {
+        component.external_references.push(ExternalReference {
+            reference_type: "website",
+            url: url.to_string(),
+        });
+    }
+    if let Some(url) = assertion(&package.package_download_location) {
+        component.external_references.push(ExternalReference {
+            reference_type: "distribution",
+            url: url.to_string(),
+        });
+    }
+
+    component
+}
+
+fn file_component(file: &FileInformation, extracted_texts: &HashMap<&str, &str>) -> Component {
+    let mut component = Component::new("file", &file.file_name);
+    component.bom_ref = Some(file.file_spdx_identifier.clone());
+    component.hashes = hashes(&file.file_checksum);
+
+    let concluded_license = file.concluded_license.to_string();
+    if assertion(&concluded_license).is_some() {
+        component.licenses = vec![LicenseChoice::Expression {
+            expression: concluded_license,
+        }];
+    }
+    component.copyright = assertion(&file.copyright_text).map(ToString::to_string);
+
+    let licenses = file
+        .license_information_in_file
+        .iter()
+        .map(ToString::to_string)
+        .filter(|license| assertion(license).is_some())
+        .map(|license| {
+            let license = if license.starts_with("LicenseRef-") {
+                License {
+                    text: extracted_texts
+                        .get(license.as_str())
+                        .map(|text| AttachedText {
+                            content_type: "text/plain",
+                            content: text.to_string(),
+                        }),
+                    id: None,
+                    name: Some(license),
+                }
+            } else {
+                License {
+                    id: Some(license),
+                    name: None,
+                    text: None,
+                }
+            };
+
+            LicenseChoice::License { license }
+        })
+        .collect::<Vec<_>>();
+
+    let copyright = assertion(&file.copyright_text)
+        .into_iter()
+        .flat_map(str::lines)
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|text| Copyright {
+            text: text.to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    if !licenses.is_empty() || !copyright.is_empty() {
+        component.evidence = Some(Evidence {
+            licenses,
+            copyright,
+        });
+    }
+
+    component
+}
+
+/// Get the dependencies of the elements from the dependency and linking relationships.
+fn dependencies(spdx: &SPDX) -> Vec<Dependency> {
+    let mut dependencies: Vec<Dependency> = Vec::new();
+
+    for relationship in &spdx.relationships {
+        let (dependent, dependency) = match relationship.relationship_type {
+            RelationshipType::DependsOn
+            | RelationshipType::DynamicLink
+            | RelationshipType::StaticLink => (
+                &relationship.spdx_element_id,
+                &relationship.related_spdx_element,
+            ),
+            RelationshipType::DependencyOf
+            | RelationshipType::BuildDependencyOf
+            | RelationshipType::DevDependencyOf
+            | RelationshipType::OptionalDependencyOf
+            | RelationshipType::ProvidedDependencyOf
+            | RelationshipType::TestDependencyOf
+            | RelationshipType::RuntimeDependencyOf => (
+                &relationship.related_spdx_element,
+                &relationship.spdx_element_id,
+            ),
+            _ => continue,
+        };
+
+        if assertion(dependent).is_none() || assertion(dependency).is_none() {
+            continue;
+        }
+
+        match dependencies
+            .iter_mut()
+            .find(|existing| &existing.dependency_ref == dependent)
+        {
+            Some(existing) => {
+                if !existing.depends_on.contains(dependency) {
+                    existing.depends_on.push(dependency.clone());
+                }
+            }
+            None => dependencies.push(Dependency {
+                dependency_ref: dependent.clone(),
+                depends_on: vec![dependency.clone()],
+            }),
+        }
+    }
+
+    dependencies
+}
+
+fn hashes(checksums: &[Checksum]) -> Vec<Hash> {
+    checksums
+        .iter()
+        .filter_map(|checksum| {
+            let alg = match checksum.algorithm {
+                Algorithm::MD5 => "MD5",
+                Algorithm::SHA1 => "SHA-1",
+                Algorithm::SHA256 => "SHA-256",
+                Algorithm::SHA384 => "SHA-384",
+                Algorithm::SHA512 => "SHA-512",
+                // Not supported by CycloneDX.
+                _ => return None,
+            };
+
+            Some(Hash {
+                alg,
+                content: checksum.value.to_lowercase(),
+            })
+        })
+        .collect()
+}
+
+/// Get the CycloneDX component type for the SPDX 2.3 primary package purpose.
+fn component_type(purpose: &str) -> &'static str {
+    match purpose {
+        "APPLICATION" => "application",
+        "FRAMEWORK" => "framework",
+        "CONTAINER" => "container",
+        "OPERATING_SYSTEM" => "operating-system",
+        "DEVICE" => "device",
+        "FIRMWARE" => "firmware",
+        "FILE" => "file",
+        _ => "library",
+    }
+}
+
+/// Get the value if it's not `NONE` or `NOASSERTION`.
+fn assertion(value: &str) -> Option<&str> {
+    match value {
+        "NONE" | "NOASSERTION" => None,
+        value => Some(value),
+    }
+}
+
+/// Get the name of a person or organization without the email address.
+fn entity_name(name: &str) -> String {
+    name.split_once('(')
+        .map_or(name, |(name, _)| name)
+        .trim()
+        .to_string()
+}
+
+/// Create a stable serial number for the SBOM from the namespace of the SPDX document, in the
+/// form of a name-based UUID.
+fn serial_number(namespace: &str) -> String {
+    let mut hash = Sha1::digest(namespace.as_bytes())[..16].to_vec();
+    hash[6] = (hash[6] & 0x0f) | 0x50;
+    hash[8] = (hash[8] & 0x3f) | 0x80;
+    let hex = hex::encode(hash);
+
+    format!(
+        "urn:uuid:{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+impl Bom {
+    /// Serialize the SBOM as JSON.
+    pub fn to_json(&self, compact: bool) -> anyhow::Result<String> {
+        if compact {
+            Ok(serde_json::to_string(self)?)
+        } else {
+            Ok(serde_json::to_string_pretty(self)?)
+        }
+    }
+
+    /// Serialize the SBOM as XML.
+    pub fn to_xml(&self) -> String {
+        let mut xml = XmlWriter::default();
+
+        xml.declaration();
+        xml.open(
+            "bom",
+            &[
+                ("xmlns", XML_NAMESPACE),
+                ("serialNumber", &self.serial_number),
+                ("version", &self.version.to_string()),
+            ],
+        );
+
+        xml.open("metadata", &[]);
+        xml.text_element("timestamp", &self.metadata.timestamp);
+        if let Some(tools) = &self.metadata.tools {
+            xml.open("tools", &[]);
+            xml.components(&tools.components);
+            xml.close("tools");
+        }
+        if !self.metadata.authors.is_empty() {
+            xml.open("authors", &[]);
+            for author in &self.metadata.authors {
+                xml.open("author", &[]);
+                xml.text_element("name", &author.name);
+                xml.close("author");
+            }
+            xml.close("authors");
+        }
+        if let Some(component) = &self.metadata.component {
+            xml.component(component);
+        }
+        xml.close("metadata");
+
+        xml.components(&self.components);
+
+        if !self.dependencies.is_empty() {
+            xml.open("dependencies", &[]);
+            for dependency in &self.dependencies {
+                xml.open("dependency", &[("ref", &dependency.dependency_ref)]);
+                for depends_on in &dependency.depends_on {
+                    xml.empty("dependency", &[("ref", depends_on)]);
+                }
+                xml.close("dependency");
+            }
+            xml.close("dependencies");
+        }
+
+        xml.close("bom");
+
+        xml.output
+    }
+}
+
+/// Writes indented XML.
+#[derive(Default)]
+struct XmlWriter {
+    output: String,
+    level: usize,
+}
+
+impl XmlWriter {
+    fn declaration(&mut self) {
+        self.output
+            .push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    }
+
+    fn start_tag(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        self.output.push_str(&"  ".repeat(self.level));
+        self.output.push('<');
+        self.output.push_str(name);
+        for (attribute, value) in attributes {
+            let _ = write!(self.output, " {}=\"{}\"", attribute, escape_html(value));
+        }
+    }
+
+    fn open(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        self.start_tag(name, attributes);
+        self.output.push_str(">\n");
+        self.level += 1;
+    }
+
+    fn empty(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        self.start_tag(name, attributes);
+        self.output.push_str("/>\n");
+    }
+
+    fn close(&mut self, name: &str) {
+        self.level -= 1;
+        let _ = writeln!(self.output, "{}</{}>", "  ".repeat(self.level), name);
+    }
+
+    fn text_element_with_attributes(
+        &mut self,
+        name: &str,
+        attributes: &[(&str, &str)],
+        text: &str,
+    ) {
+        self.start_tag(name, attributes);
+        let _ = writeln!(self.output, ">{}</{}>", escape_html(text), name);
+    }
+
+    fn text_element(&mut self, name: &str, text: &str) {
+        self.text_element_with_attributes(name, &[], text);
+    }
+
+    fn optional_text_element(&mut self, name: &str, text: &Option<String>) {
+        if let Some(text) = text {
+            self.text_element(name, text);
+        }
+    }
+
+    fn components(&mut self, components: &[Component]) {
+        if components.is_empty() {
+            return;
+        }
+
+        self.open("components", &[]);
+        for component in components {
+            self.component(component);
+        }
+        self.close("components");
+    }
+
+    /// Write the component with the child elements in the order of the XML schema.
+    fn component(&mut self, component: &Component) {
+        let mut attributes = vec![("type", component.component_type)];
+        if let Some(bom_ref) = &component.bom_ref {
+            attributes.push(("bom-ref", bom_ref));
+        }
+        self.open("component", &attributes);
+
+        if let Some(supplier) = &component.supplier {
+            self.open("supplier", &[]);
+            self.text_element("name", &supplier.name);
+            self.close("supplier");
+        }
+        self.optional_text_element("author", &component.author);
+        self.text_element("name", &component.name);
+        self.optional_text_element("version", &component.version);
+        self.optional_text_element("description", &component.description);
+
+        if !component.hashes.is_empty() {
+            self.open("hashes", &[]);
+            for hash in &component.hashes {
+                self.text_element_with_attributes("hash", &[("alg", hash.alg)], &hash.content);
+            }
+            self.close("hashes");
+        }
+
+        self.licenses(&component.licenses);
+        self.optional_text_element("copyright", &component.copyright);
+        self.optional_text_element("cpe", &component.cpe);
+        self.optional_text_element("purl", &component.purl);
+
+        if !component.external_references.is_empty() {
+            self.open("externalReferences", &[]);
+            for reference in &component.external_references {
+                self.open("reference", &[("type", reference.reference_type)]);
+                self.text_element("url", &reference.url);
+                self.close("reference");
+            }
+            self.close("externalReferences");
+        }
+
+        self.components(&component.components);
+
+        if let Some(evidence) = &component.evidence {
+            self.open("evidence", &[]);
+            self.licenses(&evidence.licenses);
+            if !evidence.copyright.is_empty() {
+                self.open("copyright", &[]);
+                for copyright in &evidence.copyright {
+                    self.text_element("text", &copyright.text);
+                }
+                self.close("copyright");
+            }
+            self.close("evidence");
+        }
+
+        self.close("component");
+    }
+
+    fn licenses(&mut self, licenses: &[LicenseChoice]) {
+        if licenses.is_empty() {
+            return;
+        }
+
+        self.open("licenses", &[]);
+        for license in licenses {
+            match license {
+                LicenseChoice::Expression { expression } => {
+                    self.text_element("expression", expression)
+                }
+                LicenseChoice::License { license } => {
+                    self.open("license", &[]);
+                    self.optional_text_element("id", &license.id);
+                    self.optional_text_element("name", &license.name);
+                    if let Some(text) = &license.text {
+                        self.text_element_with_attributes(
+                            "text",
+                            &[("content-type", text.content_type)],
+                            &text.content,
+                        );
+                    }
+                    self.close("license");
+                }
+            }
+        }
+        self.close("licenses");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use super::*;
+
+    fn example_spdx() -> SPDX {
+        serde_json::from_str(
+            &read_to_string("tests/examples/spdx/SPDXJSONExample-v2.2.spdx.json").unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn spdx_is_converted_to_cyclonedx_json() {
+        let bom = spdx_to_cyclonedx(&example_spdx());
+        let json: serde_json::Value = serde_json::from_str(&bom.to_json(false).unwrap()).unwrap();
+
+        assert_eq!(json["bomFormat"], "CycloneDX");
+        assert_eq!(json["specVersion"], "1.5");
+        assert!(json["serialNumber"]
+            .as_str()
+            .unwrap()
+            .starts_with("urn:uuid:"));
+
+        // The package described by the document is the component of the metadata.
+        let glibc = &json["metadata"]["component"];
+        assert_eq!(glibc["bom-ref"], "SPDXRef-Package");
+        assert_eq!(glibc["name"], "glibc");
+        assert_eq!(glibc["supplier"]["name"], "Jane Doe");
+        assert_eq!(
+            glibc["licenses"][0]["expression"],
+            "(LGPL-2.0-only OR LicenseRef-3)"
+        );
+        assert_eq!(glibc["hashes"][0]["alg"], "SHA-1");
+
+        // SPDXRef-JenaLib is contained in the described package, so it's a top level component.
+        let jena = json["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|component| component["bom-ref"] == "SPDXRef-JenaLib")
+            .unwrap();
+        assert_eq!(jena["type"], "file");
+        assert_eq!(
+            jena["evidence"]["licenses"][0]["license"]["name"],
+            "LicenseRef-1"
+        );
+        assert!(
+            jena["evidence"]["licenses"][0]["license"]["text"]["content"]
+                .as_str()
+                .is_some()
+        );
+
+        // Linking is a dependency.
+        assert_eq!(json["dependencies"][0]["ref"], "SPDXRef-Package");
+        assert_eq!(json["dependencies"][0]["dependsOn"][0], "SPDXRef-Saxon");
+    }
+
+    #[test]
+    fn spdx_is_converted_to_cyclonedx_xml() {
+        let xml = spdx_to_cyclonedx(&example_spdx()).to_xml();
+
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<bom xmlns="));
+        assert!(xml.contains("<component type=\"library\" bom-ref=\"SPDXRef-Package\">"));
+        assert!(xml.contains("<hash alg=\"SHA-1\">85ed0817af83a24ad8da68c2b5094de69833983c</hash>"));
+        assert!(xml.contains("<dependency ref=\"SPDXRef-Saxon\"/>"));
+        assert!(xml.trim_end().ends_with("</bom>"));
+
+        // Every opened element is closed.
+        assert_eq!(
+            xml.matches("<component ").count(),
+            xml.matches("</component>").count()
+        );
+    }
+}
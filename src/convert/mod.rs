@@ -0,0 +1,11 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Conversion of SPDX documents to other SBOM formats.
+
+mod cyclonedx;
+mod spdx3;
+
+pub use cyclonedx::{spdx_to_cyclonedx, CycloneDxFormat};
+pub use spdx3::spdx_to_spdx3;
@@ -0,0 +1,795 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Conversion of SPDX 2 documents to SPDX 3.0 JSON-LD.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+use spdx_rs::models::{
+    Checksum, ExternalPackageReferenceCategory, FileInformation, PackageInformation, Pointer,
+    Relationship, RelationshipType, Snippet, SpdxExpression, SPDX,
+};
+
+const CONTEXT: &str = "https://spdx.org/rdf/3.0.1/spdx-context.jsonld";
+const SPEC_VERSION: &str = "3.0.1";
+const CREATION_INFO_ID: &str = "_:creationinfo";
+const NONE_LICENSE: &str = "https://spdx.org/rdf/3.0.1/terms/Expanded/NoneLicense";
+
+/// Convert an SPDX 2 document to an SPDX 3.0 JSON-LD document.
+pub fn spdx_to_spdx3(spdx: &SPDX) -> Value {
+    let mut converter = Converter::new(spdx);
+
+    converter.add_creation_info();
+    for package in &spdx.package_information {
+        converter.add_package(package);
+    }
+    for file in &spdx.file_information {
+        converter.add_file(file);
+    }
+    for snippet in &spdx.snippet_information {
+        converter.add_snippet(snippet);
+    }
+    for relationship in &spdx.relationships {
+        converter.add_relationship(relationship);
+    }
+    converter.add_extracted_licenses();
+
+    converter.into_document()
+}
+
+/// Builds the `@graph` of the SPDX 3.0 document.
+struct Converter<'a> {
+    spdx: &'a SPDX,
+    namespace: String,
+    graph: Vec<Value>,
+
+    /// Ids of the elements other than the creation info and the agents.
+    elements: Vec<String>,
+
+    /// Ids of the license expression elements by the expression.
+    license_expressions: HashMap<String, String>,
+
+    /// Ids of the agents creating the document.
+    created_by: Vec<String>,
+
+    /// Ids of the tools used to create the document.
+    created_using: Vec<String>,
+
+    relationship_count: usize,
+}
+
+impl<'a> Converter<'a> {
+    fn new(spdx: &'a SPDX) -> Self {
+        Self {
+            spdx,
+            namespace: spdx
+                .document_creation_information
+                .spdx_document_namespace
+                .trim_end_matches('#')
+                .to_string(),
+            graph: Vec::new(),
+            elements: Vec::new(),
+            license_expressions: HashMap::new(),
+            created_by: Vec::new(),
+            created_using: Vec::new(),
+            relationship_count: 0,
+        }
+    }
+
+    /// Get the SPDX 3 id of an SPDX 2 identifier. References to elements in external documents are
+    /// resolved to the namespace of the external document.
+    fn id(&self, spdx_identifier: &str) -> String {
+        if let Some((document_ref, identifier)) = spdx_identifier.split_once(':') {
+            if let Some(reference) = self
+                .spdx
+                .document_creation_information
+                .external_document_references
+                .iter()
+                .find(|reference| reference.id_string == document_ref)
+            {
+                return format!(
+                    "{}#{}",
+                    reference.spdx_document_uri.trim_end_matches('#'),
+                    identifier
+                );
+            }
+        }
+
+        format!("{}#{}", self.namespace, spdx_identifier)
+    }
+
+    fn add_element(&mut self, element: Value) {
+        if let Some(id) = element["spdxId"].as_str() {
+            self.elements.push(id.to_string());
+        }
+        self.graph.push(element);
+    }
+
+    fn add_creation_info(&mut self) {
+        let creation_info = &self.spdx.document_creation_information.creation_info;
+
+        for (index, creator) in creation_info.creators.iter().enumerate() {
+            let (creator_type, name) = match creator.split_once(':') {
+                Some((creator_type, name)) => (creator_type.trim(), name.trim()),
+                None => ("Person", creator.trim()),
+            };
+            let agent_type = match creator_type {
+                "Tool" => "Tool",
+                "Organization" => "Organization",
+                _ => "Person",
+            };
+            let id = format!("{}#{}-{}", self.namespace, agent_type, index);
+
+            // Email addresses of persons and organizations are given in parentheses after the
+            // name.
+            let (name, email) = match name.split_once('(') {
+                Some((name, email)) => (name.trim(), email.trim_end_matches(')').trim()),
+                None => (name, ""),
+            };
+
+            let mut agent = element(agent_type, &id);
+            agent.insert("name".to_string(), json!(name));
+            if !email.is_empty() {
+                agent.insert(
+                    "externalIdentifier".to_string(),
+                    json!([{
+                        "type": "ExternalIdentifier",
+                        "externalIdentifierType": "email",
+                        "identifier": email,
+                    }]),
+                );
+            }
+            self.graph.push(Value::Object(agent));
+
+            if agent_type == "Tool" {
+                self.created_using.push(id);
+            } else {
+                self.created_by.push(id);
+            }
+        }
+
+        let mut creation_info_element = json!({
+            "type": "CreationInfo",
+            "@id": CREATION_INFO_ID,
+            "specVersion": SPEC_VERSION,
+            "created": creation_info.created.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            "createdBy": self.created_by,
+        });
+        if !self.created_using.is_empty() {
+            creation_info_element["createdUsing"] = json!(self.created_using);
+        }
+        if let Some(comment) = &creation_info.creator_comment {
+            creation_info_element["comment"] = json!(comment);
+        }
+        self.graph.insert(0, creation_info_element);
+    }
+
+    fn add_package(&mut self, package: &PackageInformation) {
+        let id = self.id(&package.package_spdx_identifier);
+        let mut element = element("software_Package", &id);
+
+        element.insert("name".to_string(), json!(package.package_name));
+        insert_optional(
+            &mut element,
+            "software_packageVersion",
+            package.package_version.as_deref(),
+        );
+        insert_optional(
+            &mut element,
+            "software_downloadLocation",
+            assertion(&package.package_download_location),
+        );
+        insert_optional(
+            &mut element,
+            "software_homePage",
+            package.package_home_page.as_deref().and_then(assertion),
+        );
+        insert_optional(
+            &mut element,
+            "software_copyrightText",
+            assertion(&package.copyright_text),
+        );
+        insert_optional(
+            &mut element,
+            "summary",
+            package.package_summary_description.as_deref(),
+        );
+        insert_optional(
+            &mut element,
+            "description",
+            package.package_detailed_description.as_deref(),
+        );
+        insert_optional(&mut element, "comment", package.package_comment.as_deref());
+        insert_optional(
+            &mut element,
+            "software_sourceInfo",
+            package.source_information.as_deref(),
+        );
+        insert_hashes(&mut element, &package.package_checksum);
+
+        let mut external_identifiers = Vec::new();
+        let mut external_refs = Vec::new();
+
+        for reference in &package.external_reference {
+            let locator = reference.reference_locator.as_str();
+
+            // SPDX 2.3 package fields are stored as external references when reading the
+            // document.
+            // Reference types may be given as URIs of the SPDX reference types.
+            let reference_type = reference
+                .reference_type
+                .trim_start_matches("http://spdx.org/rdf/references/");
+
+            match reference_type {
+                "SPDX-2.3:primaryPackagePurpose" => {
+                    element.insert(
+                        "software_primaryPurpose".to_string(),
+                        json!(camel_case(locator)),
+                    );
+                }
+                "SPDX-2.3:releaseDate" => {
+                    element.insert("releaseTime".to_string(), json!(locator));
+                }
+                "SPDX-2.3:builtDate" => {
+                    element.insert("builtTime".to_string(), json!(locator));
+                }
+                "SPDX-2.3:validUntilDate" => {
+                    element.insert("validUntilTime".to_string(), json!(locator));
+                }
+                "purl" => {
+                    element.insert("software_packageUrl".to_string(), json!(locator));
+                }
+                "cpe22Type" | "cpe23Type" | "swid" => {
+                    let identifier_type = match reference_type {
+                        "cpe22Type" => "cpe22",
+                        "cpe23Type" => "cpe23",
+                        _ => "swid",
+                    };
+                    external_identifiers.push(json!({
+                        "type": "ExternalIdentifier",
+                        "externalIdentifierType": identifier_type,
+                        "identifier": locator,
+                    }));
+                }
+                reference_type => {
+                    let ref_type = match (&reference.reference_category, reference_type) {
+                        (ExternalPackageReferenceCategory::Security, "advisory") => {
+                            "securityAdvisory"
+                        }
+                        (ExternalPackageReferenceCategory::Security, "fix") => "securityFix",
+                        (_, "url") => "altWebPage",
+                        (ExternalPackageReferenceCategory::PackageManager, _) => {
+                            "altDownloadLocation"
+                        }
+                        _ => "other",
+                    };
+                    let mut external_ref = json!({
+                        "type": "ExternalRef",
+                        "externalRefType": ref_type,
+                        "locator": [locator],
+                    });
+                    if let Some(comment) = &reference.reference_comment {
+                        external_ref["comment"] = json!(comment);
+                    }
+                    external_refs.push(external_ref);
+                }
+            }
+        }
+
+        if !external_identifiers.is_empty() {
+            element.insert(
+                "externalIdentifier".to_string(),
+                Value::Array(external_identifiers),
+            );
+        }
+        if !external_refs.is_empty() {
+            element.insert("externalRef".to_string(), Value::Array(external_refs));
+        }
+
+        self.add_element(Value::Object(element));
+
+        self.add_license_relationship(&id, "hasConcludedLicense", &package.concluded_license);
+        self.add_license_relationship(&id, "hasDeclaredLicense", &package.declared_license);
+    }
+
+    fn add_file(&mut self, file: &FileInformation) {
+        let id = self.id(&file.file_spdx_identifier);
+        let mut element = element("software_File", &id);
+
+        element.insert("name".to_string(), json!(file.file_name));
+        insert_optional(
+            &mut element,
+            "software_copyrightText",
+            assertion(&file.copyright_text),
+        );
+        insert_optional(&mut element, "comment", file.file_comment.as_deref());
+        insert_hashes(&mut element, &file.file_checksum);
+
+        self.add_element(Value::Object(element));
+
+        self.add_license_relationship(&id, "hasConcludedLicense", &file.concluded_license);
+
+        // The licenses found in the file are the licenses declared by the file in SPDX 3.
+        if !file.license_information_in_file.is_empty() {
+            let expression = file
+                .license_information_in_file
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            if let Ok(expression) = SpdxExpression::parse(&expression) {
+                self.add_license_relationship(&id, "hasDeclaredLicense", &expression);
+            }
+        }
+    }
+
+    fn add_snippet(&mut self, snippet: &Snippet) {
+        let id = self.id(&snippet.snippet_spdx_identifier);
+        let mut element = element("software_Snippet", &id);
+
+        insert_optional(&mut element, "name", snippet.snippet_name.as_deref());
+        element.insert(
+            "software_snippetFromFile".to_string(),
+            json!(self.id(&snippet.snippet_from_file_spdx_identifier)),
+        );
+        insert_optional(
+            &mut element,
+            "software_copyrightText",
+            assertion(&snippet.snippet_copyright_text),
+        );
+        insert_optional(&mut element, "comment", snippet.snippet_comment.as_deref());
+
+        for range in &snippet.ranges {
+            let (field, begin, end) = match (&range.start_pointer, &range.end_pointer) {
+                (Pointer::Byte { offset: begin, .. }, Pointer::Byte { offset: end, .. }) => {
+                    ("software_byteRange", begin, end)
+                }
+                (
+                    Pointer::Line {
+                        line_number: begin, ..
+                    },
+                    Pointer::Line {
+                        line_number: end, ..
+                    },
+                ) => ("software_lineRange", begin, end),
+                _ => continue,
+            };
+            element.insert(
+                field.to_string(),
+                json!({
+                    "type": "PositiveIntegerRange",
+                    "beginIntegerRange": begin,
+                    "endIntegerRange": end,
+                }),
+            );
+        }
+
+        self.add_element(Value::Object(element));
+
+        self.add_license_relationship(
+            &id,
+            "hasConcludedLicense",
+            &snippet.snippet_concluded_license,
+        );
+    }
+
+    /// Add a relationship between the element and a license expression element. Expressions
+    /// without an assertion are skipped.
+    fn add_license_relationship(
+        &mut self,
+        from: &str,
+        relationship_type: &str,
+        expression: &SpdxExpression,
+    ) {
+        let expression = expression.to_string();
+
+        let to = match expression.as_str() {
+            "NOASSERTION" => return,
+            "NONE" => NONE_LICENSE.to_string(),
+            _ => self.license_expression_id(&expression),
+        };
+
+        self.add_relationship_element(from, relationship_type, json!([to]), None);
+    }
+
+    /// Get the id of the license expression element for the expression, adding the element if it
+    /// doesn't exist yet.
+    fn license_expression_id(&mut self, expression: &str) -> String {
+        if let Some(id) = self.license_expressions.get(expression) {
+            return id.clone();
+        }
+
+        let id = format!(
+            "{}#LicenseExpression-{}",
+            self.namespace,
+            self.license_expressions.len()
+        );
+        let mut element = element("simplelicensing_LicenseExpression", &id);
+        element.insert(
+            "simplelicensing_licenseExpression".to_string(),
+            json!(expression),
+        );
+
+        let custom_licenses = SpdxExpression::parse(expression)
+            .map(|expression| expression.identifiers())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|identifier| identifier.starts_with("LicenseRef-"))
+            .map(|identifier| {
+                let uri = self.id(&identifier);
+                json!({ "type": "DictionaryEntry", "key": identifier, "value": uri })
+            })
+            .collect::<Vec<_>>();
+        if !custom_licenses.is_empty() {
+            element.insert(
+                "simplelicensing_customIdToUri".to_string(),
+                Value::Array(custom_licenses),
+            );
+        }
+
+        self.add_element(Value::Object(element));
+        self.license_expressions
+            .insert(expression.to_string(), id.clone());
+
+        id
+    }
+
+    fn add_relationship(&mut self, relationship: &Relationship) {
+        let (mut relationship_type, inverse) = relationship_type(&relationship.relationship_type);
+
+        let mut from = relationship.spdx_element_id.as_str();
+        let mut to = relationship.related_spdx_element.as_str();
+
+        // Relationships to NONE and NOASSERTION are described with completeness in SPDX 3, which
+        // can't be done for the reversed relationships.
+        let unspecified = matches!(to, "NONE" | "NOASSERTION");
+        if unspecified && inverse {
+            relationship_type = "other";
+        } else if inverse {
+            std::mem::swap(&mut from, &mut to);
+        }
+
+        let (to, completeness) = match to {
+            "NONE" => (json!([]), Some("complete")),
+            "NOASSERTION" => (json!([]), Some("noAssertion")),
+            to => (json!([self.id(to)]), None),
+        };
+
+        let from = self.id(from);
+        self.add_relationship_element(&from, relationship_type, to, completeness);
+
+        if let Some(element) = self.graph.last_mut() {
+            if let Some(comment) = &relationship.comment {
+                element["comment"] = json!(comment);
+            }
+            if relationship_type == "other" {
+                element["description"] = json!(format!(
+                    "{} {} {}",
+                    relationship.spdx_element_id,
+                    serialized_name(&relationship.relationship_type),
+                    relationship.related_spdx_element
+                ));
+            }
+        }
+    }
+
+    fn add_relationship_element(
+        &mut self,
+        from: &str,
+        relationship_type: &str,
+        to: Value,
+        completeness: Option<&str>,
+    ) {
+        let id = format!(
+            "{}#Relationship-{}",
+            self.namespace, self.relationship_count
+        );
+        self.relationship_count += 1;
+
+        let mut element = element("Relationship", &id);
+        element.insert("from".to_string(), json!(from));
+        element.insert("relationshipType".to_string(), json!(relationship_type));
+        element.insert("to".to_string(), to);
+        insert_optional(&mut element, "completeness", completeness);
+
+        self.add_element(Value::Object(element));
+    }
+
+    fn add_extracted_licenses(&mut self) {
+        for license in &self.spdx.other_licensing_information_detected {
+            let id = self.id(&license.license_identifier);
+            let mut element = element("simplelicensing_SimpleLicensingText", &id);
+
+            element.insert("name".to_string(), json!(license.license_name));
+            element.insert(
+                "simplelicensing_licenseText".to_string(),
+                json!(license.extracted_text),
+            );
+            insert_optional(&mut element, "comment", license.license_comment.as_deref());
+
+            self.add_element(Value::Object(element));
+        }
+    }
+
+    fn into_document(mut self) -> Value {
+        let document_information = &self.spdx.document_creation_information;
+        let document_id = self.id(&document_information.spdx_identifier);
+
+        // The described elements are listed both in documentDescribes and as DESCRIBES
+        // relationships.
+        let mut root_elements = document_information
+            .document_describes
+            .iter()
+            .map(|id| self.id(id))
+            .collect::<Vec<_>>();
+        for relationship in &self.spdx.relationships {
+            if relationship.relationship_type == RelationshipType::Describes
+                && relationship.spdx_element_id == document_information.spdx_identifier
+            {
+                let id = self.id(&relationship.related_spdx_element);
+                if !root_elements.contains(&id) {
+                    root_elements.push(id);
+                }
+            }
+        }
+
+        let data_license = self.license_expression_id(&document_information.data_license);
+
+        let mut document = element("SpdxDocument", &document_id);
+        document.insert(
+            "name".to_string(),
+            json!(document_information.document_name),
+        );
+        insert_optional(
+            &mut document,
+            "comment",
+            document_information.document_comment.as_deref(),
+        );
+        document.insert("dataLicense".to_string(), json!(data_license));
+        document.insert(
+            "profileConformance".to_string(),
+            json!(["core", "software", "simpleLicensing"]),
+        );
+        document.insert("rootElement".to_string(), json!(root_elements));
+        document.insert("element".to_string(), json!(self.elements));
+
+        let namespace_map = document_information
+            .external_document_references
+            .iter()
+            .map(|reference| {
+                json!({
+                    "type": "NamespaceMap",
+                    "prefix": reference.id_string,
+                    "namespace": format!("{}#", reference.spdx_document_uri.trim_end_matches('#')),
+                })
+            })
+            .collect::<Vec<_>>();
+        if !namespace_map.is_empty() {
+            document.insert("namespaceMap".to_string(), Value::Array(namespace_map));
+        }
+
+        // The document follows the creation info and the agents.
+        let position = 1 + self.created_by.len() + self.created_using.len();
+        self.graph.insert(position, Value::Object(document));
+
+        json!({
+            "@context": CONTEXT,
+            "@graph": self.graph,
+        })
+    }
+}
+
+/// Create an element with the common fields.
+fn element(element_type: &str, id: &str) -> Map<String, Value> {
+    let mut element = Map::new();
+    element.insert("type".to_string(), json!(element_type));
+    element.insert("spdxId".to_string(), json!(id));
+    element.insert("creationInfo".to_string(), json!(CREATION_INFO_ID));
+    element
+}
+
+fn insert_optional(element: &mut Map<String, Value>, field: &str, value: Option<&str>) {
+    if let Some(value) = value {
+        element.insert(field.to_string(), json!(value));
+    }
+}
+
+fn insert_hashes(element: &mut Map<String, Value>, checksums: &[Checksum]) {
+    if checksums.is_empty() {
+        return;
+    }
+
+    let hashes = checksums
+        .iter()
+        .map(|checksum| {
+            json!({
+                "type": "Hash",
+                "algorithm": serialized_name(&checksum.algorithm).to_lowercase(),
+                "hashValue": checksum.value.to_lowercase(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    element.insert("verifiedUsing".to_string(), Value::Array(hashes));
+}
+
+/// Get the value if it's not `NONE` or `NOASSERTION`.
+fn assertion(value: &str) -> Option<&str> {
+    match value {
+        "NONE" | "NOASSERTION" => None,
+        value => Some(value),
+    }
+}
+
+/// Get the name of the enum variant as serialized in the SPDX 2 document.
+fn serialized_name<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(ToString::to_string))
+        .unwrap_or_default()
+}
+
+/// Convert an SPDX 2 enum value like `OPERATING_SYSTEM` to the SPDX 3 form `operatingSystem`.
+fn camel_case(value: &str) -> String {
+    value
+        .split(['_', '-'])
+        .enumerate()
+        .map(|(index, word)| {
+            let word = word.to_lowercase();
+            match index {
+                0 => word,
+                _ => {
+                    let mut chars = word.chars();
+                    chars
+                        .next()
+                        .map(|first| first.to_uppercase().chain(chars).collect())
+                        .unwrap_or_default()
+                }
+            }
+        })
+        .collect()
+}
+
+/// Get the SPDX 3 relationship type for the SPDX 2 relationship type, and whether the direction of
+/// the relationship is reversed in SPDX 3.
+fn relationship_type(relationship_type: &RelationshipType) -> (&'static str, bool) {
+    use RelationshipType::*;
+
+    match relationship_type {
+        Describes => ("describes", false),
+        DescribedBy => ("describes", true),
+        Contains => ("contains", false),
+        ContainedBy => ("contains", true),
+        DependsOn => ("dependsOn", false),
+        DependencyOf | BuildDependencyOf | DevDependencyOf | OptionalDependencyOf
+        | ProvidedDependencyOf | TestDependencyOf | RuntimeDependencyOf => ("dependsOn", true),
+        DependencyManifestOf => ("hasDependencyManifest", true),
+        ExampleOf => ("hasExample", true),
+        Generates => ("generates", false),
+        GeneratedFrom => ("generates", true),
+        AncestorOf => ("ancestorOf", false),
+        DescendantOf => ("descendantOf", false),
+        VariantOf => ("hasVariant", true),
+        DistributionArtifact => ("hasDistributionArtifact", false),
+        PatchFor | PatchApplied => ("patchedBy", true),
+        CopyOf => ("copiedTo", true),
+        FileAdded => ("hasAddedFile", true),
+        FileDeleted => ("hasDeletedFile", true),
+        FileModified => ("modifiedBy", true),
+        ExpandedFromArchive => ("expandsTo", true),
+        DynamicLink => ("hasDynamicLink", false),
+        StaticLink => ("hasStaticLink", false),
+        DataFileOf => ("hasDataFile", true),
+        TestCaseOf => ("hasTestCase", true),
+        BuildToolOf | DevToolOf | TestToolOf => ("usesTool", true),
+        TestOf => ("hasTest", true),
+        DocumentationOf => ("hasDocumentation", true),
+        OptionalComponentOf => ("hasOptionalComponent", true),
+        MetafileOf => ("hasMetadata", true),
+        Amends => ("amendedBy", true),
+        PrerequisiteFor => ("hasPrerequisite", true),
+        HasPrerequisite => ("hasPrerequisite", false),
+        PackageOf | Other => ("other", false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use super::*;
+
+    fn elements_of_type<'a>(document: &'a Value, element_type: &str) -> Vec<&'a Value> {
+        document["@graph"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|element| element["type"] == element_type)
+            .collect()
+    }
+
+    #[test]
+    fn spdx_2_2_document_is_converted() {
+        let spdx: SPDX = serde_json::from_str(
+            &read_to_string("tests/examples/spdx/SPDXJSONExample-v2.2.spdx.json").unwrap(),
+        )
+        .unwrap();
+
+        let document = spdx_to_spdx3(&spdx);
+        let namespace = &spdx.document_creation_information.spdx_document_namespace;
+
+        assert_eq!(document["@context"], CONTEXT);
+        assert_eq!(document["@graph"][0]["@id"], CREATION_INFO_ID);
+
+        let spdx_document = elements_of_type(&document, "SpdxDocument");
+        assert_eq!(spdx_document.len(), 1);
+
+        let packages = elements_of_type(&document, "software_Package");
+        assert_eq!(packages.len(), spdx.package_information.len());
+        assert_eq!(
+            elements_of_type(&document, "software_File").len(),
+            spdx.file_information.len()
+        );
+
+        let package = packages
+            .iter()
+            .find(|package| package["spdxId"] == format!("{}#SPDXRef-Package", namespace))
+            .unwrap();
+        assert_eq!(package["software_packageVersion"], "2.11.1");
+        assert_eq!(
+            package["externalIdentifier"][0]["externalIdentifierType"],
+            "cpe23"
+        );
+        assert_eq!(package["verifiedUsing"][1]["algorithm"], "md5");
+
+        // Every element in the document is listed in the SpdxDocument.
+        let listed = spdx_document[0]["element"].as_array().unwrap();
+        for element in document["@graph"].as_array().unwrap() {
+            if let Some(id) = element["spdxId"].as_str() {
+                let element_type = element["type"].as_str().unwrap();
+                if !matches!(
+                    element_type,
+                    "SpdxDocument" | "Tool" | "Organization" | "Person"
+                ) {
+                    assert!(listed.contains(&json!(id)), "{} is not listed", id);
+                }
+            }
+        }
+
+        // License expressions are only added once.
+        let expressions = elements_of_type(&document, "simplelicensing_LicenseExpression");
+        let mut unique = expressions
+            .iter()
+            .map(|expression| expression["simplelicensing_licenseExpression"].as_str())
+            .collect::<Vec<_>>();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), expressions.len());
+    }
+
+    #[test]
+    fn inverse_relationships_are_reversed() {
+        let spdx: SPDX = serde_json::from_str(
+            &read_to_string("tests/examples/spdx/SPDXJSONExample-v2.2.spdx.json").unwrap(),
+        )
+        .unwrap();
+        let namespace = &spdx.document_creation_information.spdx_document_namespace;
+
+        let document = spdx_to_spdx3(&spdx);
+
+        // SPDXRef-File GENERATED_FROM SPDXRef-fromDoap-0 in the SPDX 2 document.
+        assert!(elements_of_type(&document, "Relationship").iter().any(
+            |relationship| relationship["relationshipType"] == "generates"
+                && relationship["from"] == format!("{}#SPDXRef-fromDoap-0", namespace)
+                && relationship["to"][0] == format!("{}#SPDXRef-File", namespace)
+        ));
+
+        assert_eq!(camel_case("OPERATING_SYSTEM"), "operatingSystem");
+        assert_eq!(
+            relationship_type(&RelationshipType::GeneratedFrom),
+            ("generates", true)
+        );
+    }
+}
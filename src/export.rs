@@ -0,0 +1,300 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Export package and file findings of SPDX documents as spreadsheets.
+
+use std::{collections::HashMap, io::Write, path::Path};
+
+use clap::ValueEnum;
+use log::info;
+use spdx_rs::models::{Checksum, FileInformation, PackageInformation, SPDX};
+
+use crate::{spdx::algorithm_name, utilities::files_by_package};
+
+/// Column of the exported spreadsheet.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    /// Type of the row, either `Package` or `File`.
+    Type,
+    SpdxId,
+    Name,
+    Version,
+    Declared,
+    Concluded,
+    LicenseInfoInFile,
+    Copyright,
+    Checksums,
+    /// Packages containing the file.
+    Package,
+}
+
+impl Column {
+    /// All columns in the default order.
+    pub const ALL: [Column; 10] = [
+        Column::Type,
+        Column::SpdxId,
+        Column::Name,
+        Column::Version,
+        Column::Declared,
+        Column::Concluded,
+        Column::LicenseInfoInFile,
+        Column::Copyright,
+        Column::Checksums,
+        Column::Package,
+    ];
+
+    /// Header of the column.
+    pub fn header(&self) -> &'static str {
+        match self {
+            Column::Type => "Type",
+            Column::SpdxId => "SPDX ID",
+            Column::Name => "Name",
+            Column::Version => "Version",
+            Column::Declared => "Declared license",
+            Column::Concluded => "Concluded license",
+            Column::LicenseInfoInFile => "License info in file",
+            Column::Copyright => "Copyright",
+            Column::Checksums => "Checksums",
+            Column::Package => "Package",
+        }
+    }
+
+    /// Value of the column for a package.
+    fn package_value(&self, package: &PackageInformation) -> String {
+        match self {
+            Column::Type => "Package".to_string(),
+            Column::SpdxId => package.package_spdx_identifier.clone(),
+            Column::Name => package.package_name.clone(),
+            Column::Version => package.package_version.clone().unwrap_or_default(),
+            Column::Declared => package.declared_license.to_string(),
+            Column::Concluded => package.concluded_license.to_string(),
+            Column::LicenseInfoInFile => package.all_licenses_information_from_files.join(", "),
+            Column::Copyright => package.copyright_text.clone(),
+            Column::Checksums => format_checksums(&package.package_checksum),
+            Column::Package => String::new(),
+        }
+    }
+
+    /// Value of the column for a file.
+    fn file_value(&self, file: &FileInformation, packages: &[&PackageInformation]) -> String {
+        match self {
+            Column::Type => "File".to_string(),
+            Column::SpdxId => file.file_spdx_identifier.clone(),
+            Column::Name => file.file_name.clone(),
+            Column::Version | Column::Declared => String::new(),
+            Column::Concluded => file.concluded_license.to_string(),
+            Column::LicenseInfoInFile => file
+                .license_information_in_file
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            Column::Copyright => file.copyright_text.clone(),
+            Column::Checksums => format_checksums(&file.file_checksum),
+            Column::Package => packages
+                .iter()
+                .map(|package| package.package_name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+/// Create the rows of the spreadsheet with one row per package and one per file. The first row
+/// is the header.
+pub fn spreadsheet_rows(spdx: &SPDX, columns: &[Column]) -> Vec<Vec<String>> {
+    let mut packages_by_file: HashMap<&str, Vec<&PackageInformation>> = HashMap::new();
+    let files_by_package = files_by_package(spdx);
+
+    for package in &spdx.package_information {
+        if let Some(files) = files_by_package.get(package.package_spdx_identifier.as_str()) {
+            for file in files {
+                packages_by_file
+                    .entry(file.file_spdx_identifier.as_str())
+                    .or_default()
+                    .push(package);
+            }
+        }
+    }
+
+    let mut rows = vec![columns
+        .iter()
+        .map(|column| column.header().to_string())
+        .collect::<Vec<_>>()];
+
+    for package in &spdx.package_information {
+        rows.push(
+            columns
+                .iter()
+                .map(|column| column.package_value(package))
+                .collect(),
+        );
+    }
+
+    for file in &spdx.file_information {
+        let packages = packages_by_file
+            .get(file.file_spdx_identifier.as_str())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        rows.push(
+            columns
+                .iter()
+                .map(|column| column.file_value(file, packages))
+                .collect(),
+        );
+    }
+
+    rows
+}
+
+/// Write the rows as CSV.
+pub fn write_csv<W: Write>(writer: W, rows: &[Vec<String>]) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+
+    for row in rows {
+        writer.write_record(row)?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Write the rows to an XLSX workbook at path.
+#[cfg(feature = "xlsx")]
+pub fn write_xlsx<P: AsRef<Path>>(path: P, rows: &[Vec<String>]) -> anyhow::Result<()> {
+    use rust_xlsxwriter::{Format, Workbook};
+
+    let mut workbook = Workbook::new();
+    let header_format = Format::new().set_bold();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("SPDX")?;
+
+    for (row_number, row) in rows.iter().enumerate() {
+        for (column_number, value) in row.iter().enumerate() {
+            if row_number == 0 {
+                worksheet.write_string_with_format(
+                    row_number as u32,
+                    column_number as u16,
+                    value,
+                    &header_format,
+                )?;
+            } else {
+                worksheet.write_string(row_number as u32, column_number as u16, value)?;
+            }
+        }
+    }
+
+    worksheet.set_freeze_panes(1, 0)?;
+    workbook.save(path)?;
+
+    Ok(())
+}
+
+/// Export the [`SPDX`] to a spreadsheet at path. The format is selected by the extension of the
+/// path.
+pub fn export_spdx<P: AsRef<Path>>(
+    spdx: &SPDX,
+    output_path: P,
+    columns: &[Column],
+) -> anyhow::Result<()> {
+    info!("Exporting SPDX to {}.", output_path.as_ref().display());
+
+    let rows = spreadsheet_rows(spdx, columns);
+
+    match output_path
+        .as_ref()
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("csv") => write_csv(std::fs::File::create(&output_path)?, &rows),
+        #[cfg(feature = "xlsx")]
+        Some("xlsx") => write_xlsx(&output_path, &rows),
+        #[cfg(not(feature = "xlsx"))]
+        Some("xlsx") => Err(anyhow::anyhow!("built without XLSX support")),
+        _ => Err(anyhow::anyhow!("invalid file extension")),
+    }
+}
+
+/// Format checksums as `ALGORITHM: value` separated by commas.
+fn format_checksums(checksums: &[Checksum]) -> String {
+    checksums
+        .iter()
+        .map(|checksum| format!("{}: {}", algorithm_name(checksum.algorithm), checksum.value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use spdx_rs::models::{Algorithm, Relationship, RelationshipType, SpdxExpression};
+
+    use super::*;
+
+    fn test_spdx() -> SPDX {
+        let mut spdx = SPDX::new("test_spdx");
+
+        spdx.package_information = vec![PackageInformation {
+            package_name: "zlib".to_string(),
+            package_spdx_identifier: "SPDXRef-zlib".to_string(),
+            package_version: Some("1.2.12".to_string()),
+            declared_license: SpdxExpression::parse("Zlib").unwrap(),
+            ..Default::default()
+        }];
+        spdx.file_information = vec![FileInformation {
+            file_name: "zlib.c".to_string(),
+            file_spdx_identifier: "SPDXRef-File-1".to_string(),
+            file_checksum: vec![Checksum::new(Algorithm::SHA256, "ABC")],
+            copyright_text: "Copyright Jean-loup Gailly, Mark Adler".to_string(),
+            ..Default::default()
+        }];
+        spdx.relationships = vec![Relationship::new(
+            "SPDXRef-zlib",
+            "SPDXRef-File-1",
+            RelationshipType::Contains,
+            None,
+        )];
+
+        spdx
+    }
+
+    #[test]
+    fn rows_contain_selected_columns() {
+        let spdx = test_spdx();
+
+        let rows = spreadsheet_rows(
+            &spdx,
+            &[
+                Column::Type,
+                Column::Name,
+                Column::Checksums,
+                Column::Package,
+            ],
+        );
+
+        assert_eq!(
+            rows,
+            vec![
+                vec!["Type", "Name", "Checksums", "Package"],
+                vec!["Package", "zlib", "", ""],
+                vec!["File", "zlib.c", "SHA256: abc", "zlib"],
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_is_written_correctly() {
+        let spdx = test_spdx();
+        let rows = spreadsheet_rows(&spdx, &[Column::Name, Column::Copyright]);
+
+        let mut output = Vec::new();
+        write_csv(&mut output, &rows).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Name,Copyright\nzlib,NOASSERTION\nzlib.c,\"Copyright Jean-loup Gailly, Mark Adler\"\n"
+        );
+    }
+}
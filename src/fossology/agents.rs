@@ -0,0 +1,188 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Configuration of the agents scheduled for the archives uploaded to Fossology.
+
+use std::{fs::read_to_string, path::Path};
+
+use clap::ValueEnum;
+use fossology_rs::job::ScheduleAgents;
+use serde::Deserialize;
+
+/// Analysis agents of Fossology.
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Agent {
+    Bucket,
+    CopyrightEmailAuthor,
+    Ecc,
+    Keyword,
+    Mime,
+    Monk,
+    Nomos,
+    Ojo,
+    Package,
+}
+
+/// Deciders of Fossology that conclude licenses based on the findings of the agents.
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Decider {
+    /// Conclude licenses found by both nomos and monk.
+    NomosMonk,
+
+    /// Conclude licenses only if no new findings since the previous scan.
+    NewScanner,
+
+    /// Conclude licenses found by ojo.
+    Ojo,
+}
+
+/// Settings for reusing the clearing decisions of an earlier upload.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReuseConfig {
+    /// ID of the upload to reuse the conclusions of.
+    pub upload: Option<i32>,
+
+    /// Reuse the conclusions of the most recent earlier upload of the same package in the target
+    /// folder if `upload` is not set.
+    pub previous_version: bool,
+
+    /// Group of the upload to reuse.
+    pub group: String,
+
+    /// Reuse the main license of the upload.
+    pub main: bool,
+
+    /// Reuse the conclusions for files that differ only slightly from the reused files.
+    pub enhanced: bool,
+
+    /// Reuse the report configuration of the upload.
+    pub report: bool,
+
+    /// Reuse the copyright decisions of the upload.
+    pub copyright: bool,
+}
+
+impl Default for ReuseConfig {
+    fn default() -> Self {
+        Self {
+            upload: None,
+            previous_version: false,
+            group: "fossy".to_string(),
+            main: true,
+            enhanced: false,
+            report: true,
+            copyright: true,
+        }
+    }
+}
+
+/// Agents, deciders and reuse settings to schedule for the uploads. Read from a YAML or JSON file,
+/// e.g.
+///
+/// ```yaml
+/// agents: [nomos, ojo, copyright-email-author]
+/// deciders: [nomos-monk]
+/// reuse:
+///   upload: 42
+///   enhanced: true
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalysisConfig {
+    pub agents: Vec<Agent>,
+    pub deciders: Vec<Decider>,
+    pub reuse: ReuseConfig,
+}
+
+impl Default for AnalysisConfig {
+    /// Full scan with all agents and deciders.
+    fn default() -> Self {
+        Self {
+            agents: Agent::value_variants().to_vec(),
+            deciders: Decider::value_variants().to_vec(),
+            reuse: ReuseConfig::default(),
+        }
+    }
+}
+
+impl AnalysisConfig {
+    /// Read the config from a YAML or JSON file. Settings missing from the file use the defaults.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        Ok(serde_yaml::from_str(&read_to_string(path)?)?)
+    }
+
+    /// Convert the config to the input of Fossology's analysis endpoint.
+    pub fn schedule_agents(&self) -> ScheduleAgents {
+        let mut analysis_input = ScheduleAgents::default();
+
+        for agent in &self.agents {
+            let analysis = &mut analysis_input.analysis;
+            match agent {
+                Agent::Bucket => analysis.bucket = true,
+                Agent::CopyrightEmailAuthor => analysis.copyright_email_author = true,
+                Agent::Ecc => analysis.ecc = true,
+                Agent::Keyword => analysis.keyword = true,
+                Agent::Mime => analysis.mime = true,
+                Agent::Monk => analysis.monk = true,
+                Agent::Nomos => analysis.nomos = true,
+                Agent::Ojo => analysis.ojo = true,
+                Agent::Package => analysis.package = true,
+            }
+        }
+
+        for decider in &self.deciders {
+            let deciders = &mut analysis_input.decider;
+            match decider {
+                Decider::NomosMonk => deciders.nomos_monk = true,
+                Decider::NewScanner => deciders.new_scanner = true,
+                Decider::Ojo => deciders.ojo_decider = true,
+            }
+        }
+
+        if let Some(upload) = self.reuse.upload {
+            let reuse = &mut analysis_input.reuse;
+            reuse.reuse_upload = upload;
+            reuse.reuse_group = self.reuse.group.clone();
+            reuse.reuse_main = self.reuse.main;
+            reuse.reuse_enhanced = self.reuse.enhanced;
+            reuse.reuse_report = self.reuse.report;
+            reuse.reuse_copyright = self.reuse.copyright;
+        }
+
+        analysis_input
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_schedules_full_scan() {
+        let agents = AnalysisConfig::default().schedule_agents();
+
+        assert!(agents.analysis.nomos && agents.analysis.package && agents.analysis.bucket);
+        assert!(agents.decider.nomos_monk && agents.decider.new_scanner);
+        assert!(agents.decider.ojo_decider);
+        assert_eq!(agents.reuse.reuse_upload, 0);
+    }
+
+    #[test]
+    fn config_is_read_from_yaml() {
+        let config: AnalysisConfig = serde_yaml::from_str(
+            "agents: [nomos, ojo]\ndeciders: []\nreuse:\n  upload: 42\n  enhanced: true\n",
+        )
+        .unwrap();
+
+        let agents = config.schedule_agents();
+        assert!(agents.analysis.nomos && agents.analysis.ojo);
+        assert!(!agents.analysis.monk && !agents.decider.nomos_monk);
+        assert_eq!(agents.reuse.reuse_upload, 42);
+        assert!(agents.reuse.reuse_enhanced && agents.reuse.reuse_main);
+        assert_eq!(agents.reuse.reuse_group, "fossy");
+    }
+}
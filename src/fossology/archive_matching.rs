@@ -0,0 +1,389 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Matching source archives to the packages in SPDX, e.g. to decide which archives are not
+//! uploaded.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    fs::read_to_string,
+    path::Path,
+};
+
+use spdx_rs::models::PackageInformation;
+
+use super::doubleopen_licenses::package_name_from_archive;
+
+/// Why an archive was matched to a package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchReason {
+    /// The file name of the package is the file name of the archive.
+    PackageFileName,
+
+    /// The download location of the package points to the archive.
+    DownloadLocation(String),
+
+    /// A `SRC_URI` entry of the recipe of the package points to the archive.
+    SrcUri(String),
+
+    /// The file name of the archive starts with the name and the version of the package.
+    NameAndVersion,
+}
+
+impl Display for MatchReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchReason::PackageFileName => write!(f, "package file name"),
+            MatchReason::DownloadLocation(location) => write!(f, "download location {}", location),
+            MatchReason::SrcUri(uri) => write!(f, "SRC_URI {}", uri),
+            MatchReason::NameAndVersion => write!(f, "name and version"),
+        }
+    }
+}
+
+/// Why an archive is not uploaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The archive is on the exclude list.
+    Excluded,
+
+    /// The archive is the source of a package that should not be uploaded.
+    Package {
+        name: String,
+        version: Option<String>,
+        reason: MatchReason,
+    },
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Excluded => write!(f, "excluded explicitly"),
+            SkipReason::Package {
+                name,
+                version,
+                reason,
+            } => {
+                write!(f, "source of package {}", name)?;
+                if let Some(version) = version {
+                    write!(f, " {}", version)?;
+                }
+                write!(f, " matched by {}", reason)
+            }
+        }
+    }
+}
+
+/// Settings for matching source archives to the packages in SPDX.
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveMatching {
+    /// File names of archives to upload even if they match a package that should not be uploaded.
+    pub include: Vec<String>,
+
+    /// File names of archives never to upload.
+    pub exclude: Vec<String>,
+
+    /// `SRC_URI` entries of Yocto recipes by the name of the package.
+    pub src_uris: HashMap<String, Vec<String>>,
+}
+
+impl ArchiveMatching {
+    /// Read the `SRC_URI` entries of Yocto recipes from a YAML or JSON file mapping package names
+    /// to lists of URIs.
+    pub fn read_src_uris<P: AsRef<Path>>(path: P) -> anyhow::Result<HashMap<String, Vec<String>>> {
+        Ok(serde_yaml::from_str(&read_to_string(path)?)?)
+    }
+
+    /// Get the reason the archive matches the package, if it does.
+    pub fn match_reason(
+        &self,
+        archive_path: &Path,
+        package: &PackageInformation,
+    ) -> Option<MatchReason> {
+        let file_name = archive_path.file_name()?.to_string_lossy();
+
+        if package.package_file_name.as_deref() == Some(&file_name) {
+            return Some(MatchReason::PackageFileName);
+        }
+
+        if uri_file_name(&package.package_download_location) == Some(&file_name) {
+            return Some(MatchReason::DownloadLocation(
+                package.package_download_location.clone(),
+            ));
+        }
+
+        let src_uris = self.src_uris.get(&package.package_name);
+        if let Some(uri) = src_uris
+            .into_iter()
+            .flatten()
+            .find(|uri| uri_file_name(uri) == Some(&file_name))
+        {
+            return Some(MatchReason::SrcUri(uri.clone()));
+        }
+
+        if archive_matches_package(archive_path, package) {
+            return Some(MatchReason::NameAndVersion);
+        }
+
+        None
+    }
+
+    /// Find the package the archive is the source of. Matches by the file name, download location
+    /// or `SRC_URI` are preferred over matches by the name and version, and the package with the
+    /// longest name is used if the archive still matches multiple packages.
+    pub fn package_for_archive<'a>(
+        &self,
+        archive_path: &Path,
+        packages: &'a [PackageInformation],
+    ) -> Option<&'a PackageInformation> {
+        packages
+            .iter()
+            .filter_map(|package| {
+                let reason = self.match_reason(archive_path, package)?;
+                let exact = reason != MatchReason::NameAndVersion;
+                Some(((exact, package.package_name.len()), package))
+            })
+            .max_by_key(|(rank, _)| *rank)
+            .map(|(_, package)| package)
+    }
+
+    /// Get the reason the archive should not be uploaded, or `None` if it should be uploaded.
+    pub fn skip_reason(
+        &self,
+        archive_path: &Path,
+        packages_to_skip: &[&PackageInformation],
+    ) -> Option<SkipReason> {
+        let file_name = archive_path.file_name()?.to_string_lossy();
+
+        if self.exclude.iter().any(|exclude| exclude == &file_name) {
+            return Some(SkipReason::Excluded);
+        }
+
+        if self.include.iter().any(|include| include == &file_name) {
+            return None;
+        }
+
+        packages_to_skip.iter().find_map(|package| {
+            self.match_reason(archive_path, package)
+                .map(|reason| SkipReason::Package {
+                    name: package.package_name.clone(),
+                    version: package.package_version.clone(),
+                    reason,
+                })
+        })
+    }
+}
+
+/// Check if the file name of the archive at path matches the name and version of the package.
+/// The name must be followed by the version, so that e.g. package `foo` doesn't match
+/// `foo-bar-utils-1.0.tar.gz`. Packages without a version match archives whose package name parsed
+/// with [`package_name_from_archive`] is the name of the package, and archives named only after
+/// the package, like `foo.tar.gz`.
+fn archive_matches_package(archive_path: &Path, package: &PackageInformation) -> bool {
+    let file_name = match archive_path.file_name() {
+        Some(file_name) => file_name.to_string_lossy(),
+        None => return false,
+    };
+
+    let version = match &package.package_version {
+        Some(version) if !version.is_empty() => version,
+        _ => {
+            let name_only = file_name
+                .strip_prefix(package.package_name.as_str())
+                .is_some_and(|rest| rest.starts_with('.'));
+
+            return name_only
+                || package_name_from_archive(archive_path).as_ref() == Some(&package.package_name);
+        }
+    };
+
+    let remainder = file_name
+        .strip_prefix(package.package_name.as_str())
+        .and_then(|rest| rest.strip_prefix(&['-', '_'][..]))
+        .and_then(|rest| rest.strip_prefix(version.as_str()));
+
+    // The version must not continue, e.g. 1.16 must not match 1.16.1, but 1.16.tar.gz is fine.
+    match remainder {
+        Some(remainder) => {
+            let mut chars = remainder.chars();
+            match chars.next() {
+                None | Some('-') | Some('_') | Some('+') | Some('~') => true,
+                Some('.') => !chars.next().is_some_and(|c| c.is_ascii_digit()),
+                Some(_) => false,
+            }
+        }
+        None => false,
+    }
+}
+
+/// Get the file name of the URI, ignoring the parameters of Yocto `SRC_URI`s, queries and
+/// fragments.
+fn uri_file_name(uri: &str) -> Option<&str> {
+    let uri = uri.split(&[';', '?', '#'][..]).next()?;
+
+    if !uri.contains("://") {
+        return None;
+    }
+
+    uri.rsplit('/')
+        .next()
+        .filter(|file_name| !file_name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, version: &str, download_location: &str) -> PackageInformation {
+        PackageInformation {
+            package_name: name.to_string(),
+            package_version: Some(version.to_string()),
+            package_download_location: download_location.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn archives_are_matched_by_download_location_and_src_uri() {
+        let mut matching = ArchiveMatching::default();
+        matching.src_uris.insert(
+            "tzdata".to_string(),
+            vec![
+                "https://data.iana.org/time-zones/releases/tzdata2021a.tar.gz;name=tzdata"
+                    .to_string(),
+                "file://0001-fix.patch".to_string(),
+            ],
+        );
+
+        let openssl = package(
+            "openssl",
+            "3.0.2",
+            "https://www.openssl.org/source/openssl-3.0.2.tar.gz",
+        );
+        let tzdata = package("tzdata", "2021a", "NOASSERTION");
+
+        assert_eq!(
+            matching.match_reason(Path::new("dl/openssl-3.0.2.tar.gz"), &openssl),
+            Some(MatchReason::DownloadLocation(
+                openssl.package_download_location.clone()
+            ))
+        );
+        assert!(matches!(
+            matching.match_reason(Path::new("tzdata2021a.tar.gz"), &tzdata),
+            Some(MatchReason::SrcUri(_))
+        ));
+        assert_eq!(
+            matching.match_reason(Path::new("tzdata-2021a-r0.tar.bz2"), &tzdata),
+            Some(MatchReason::NameAndVersion)
+        );
+        assert_eq!(
+            matching.match_reason(Path::new("0001-fix.patch"), &tzdata),
+            Some(MatchReason::SrcUri("file://0001-fix.patch".to_string()))
+        );
+        assert_eq!(
+            matching.match_reason(Path::new("0002-other.patch"), &tzdata),
+            None
+        );
+    }
+
+    #[test]
+    fn overrides_take_precedence_over_packages() {
+        let closed = package("foo", "1.0", "NOASSERTION");
+        let matching = ArchiveMatching {
+            include: vec!["foo-1.0-r0.tar.gz".to_string()],
+            exclude: vec!["bar-2.0-r0.tar.gz".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            matching.skip_reason(Path::new("foo-1.0-r0.tar.gz"), &[&closed]),
+            None
+        );
+        assert_eq!(
+            matching.skip_reason(Path::new("bar-2.0-r0.tar.gz"), &[&closed]),
+            Some(SkipReason::Excluded)
+        );
+        assert_eq!(
+            matching
+                .skip_reason(Path::new("foo-1.0-r1.tar.gz"), &[&closed])
+                .unwrap()
+                .to_string(),
+            "source of package foo 1.0 matched by name and version"
+        );
+        assert_eq!(
+            matching.skip_reason(Path::new("foo-bar-utils-1.0.tar.gz"), &[&closed]),
+            None
+        );
+    }
+
+    #[test]
+    fn archive_matches_only_exact_package_name_and_version() {
+        let package = |name: &str, version: Option<&str>| PackageInformation {
+            package_name: name.to_string(),
+            package_version: version.map(ToString::to_string),
+            ..Default::default()
+        };
+
+        let foo = package("foo", Some("1.0"));
+        assert!(archive_matches_package(Path::new("foo-1.0.tar.gz"), &foo));
+        assert!(archive_matches_package(
+            Path::new("foo-1.0-r0.tar.gz"),
+            &foo
+        ));
+        assert!(!archive_matches_package(
+            Path::new("foo-bar-utils-1.0.tar.gz"),
+            &foo
+        ));
+        assert!(!archive_matches_package(
+            Path::new("foo-1.0.1.tar.gz"),
+            &foo
+        ));
+        assert!(!archive_matches_package(Path::new("foo-1.01.tar.gz"), &foo));
+
+        let unversioned = package("c++utils", None);
+        assert!(archive_matches_package(
+            Path::new("c++utils-2.1.tar.gz"),
+            &unversioned
+        ));
+        assert!(!archive_matches_package(
+            Path::new("c++utils-extra-2.1.tar.gz"),
+            &unversioned
+        ));
+
+        let firmware = package("firmware", None);
+        assert!(archive_matches_package(
+            Path::new("firmware.tar.gz"),
+            &firmware
+        ));
+        assert!(!archive_matches_package(
+            Path::new("firmware-tools.tar.gz"),
+            &firmware
+        ));
+    }
+
+    #[test]
+    fn exact_matches_are_preferred_when_finding_the_package() {
+        let matching = ArchiveMatching::default();
+        let packages = vec![
+            package("foo", "1.0", "NOASSERTION"),
+            package(
+                "foo-source",
+                "2.0",
+                "https://example.com/releases/foo-1.0.tar.gz",
+            ),
+        ];
+
+        assert_eq!(
+            matching
+                .package_for_archive(Path::new("foo-1.0.tar.gz"), &packages)
+                .map(|package| package.package_name.as_str()),
+            Some("foo-source")
+        );
+        assert_eq!(
+            matching.package_for_archive(Path::new("bar-1.0.tar.gz"), &packages),
+            None
+        );
+    }
+}
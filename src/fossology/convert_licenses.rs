@@ -14,12 +14,12 @@ pub fn license_information_to_spdx_expressions(
 ) -> anyhow::Result<Vec<SimpleExpression>> {
     license_information
         .iter()
-        .cloned()
         .filter(|lic| !lic.starts_with("DOLicense"))
         // Remove No_license_found
-        .filter(|lic| lic != "No_license_found")
+        .filter(|&lic| lic != "No_license_found")
         // Remove Dual-license
-        .filter(|lic| lic != "Dual-license")
+        .filter(|&lic| lic != "Dual-license")
+        .cloned()
         .map(gpl_or_later_conversion)
         // Sanitize characters
         .map(sanitize_spdx_expression)
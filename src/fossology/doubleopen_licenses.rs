@@ -5,7 +5,7 @@
 use std::path::Path;
 
 use regex::Regex;
-use spdx_rs::models::{PackageInformation, SpdxExpression};
+use spdx_rs::models::SpdxExpression;
 use spdx_toolkit::license_list::LicenseList;
 
 /// Parse list of Double Open's license conclusions from Fossology to an SPDX expression.
@@ -197,51 +197,25 @@ fn add_licenserefs(conclusions: Vec<String>, license_list: &LicenseList) -> Vec<
         .collect()
 }
 
-/// Check if the package contains a declared license with "CLOSED".
-pub fn get_packages_with_closed_license(
-    package_information: &[PackageInformation],
-) -> Vec<&PackageInformation> {
-    package_information
-        .iter()
-        .filter(|package| package.declared_license.to_string().contains("CLOSED"))
-        .collect()
-}
-
-/// Check if the archive at path should not be uploaded.
-pub fn skip_package_upload<P: AsRef<Path>>(
-    archive_path: P,
-    packages_to_skip: &[&PackageInformation],
-) -> bool {
-    let mut packages_to_skip_regex = packages_to_skip.iter().map(|package| {
-        if let Some(version) = &package.package_version {
-            Regex::new(&format!(
-                "^{}.*{}.*",
-                regex::escape(&package.package_name),
-                regex::escape(version)
-            ))
-            .expect("Regex creation to succeed")
-        } else {
-            Regex::new(&format!("^{}.*", &package.package_name)).expect("Regex creation to succeed")
-        }
-    });
+/// Get the name of the package from the file name of the archive, e.g. `busybox` from
+/// `busybox-1.35.0-r0.tar.bz2`. The name is the part before the version, which starts with a digit
+/// or with `git` for packages built from git revisions.
+pub fn package_name_from_archive<P: AsRef<Path>>(archive_path: P) -> Option<String> {
+    let name_regex = Regex::new(r"^(.+?)[-_](?:v?\d|git)").expect("Regex creation to succeed");
 
-    packages_to_skip_regex.any(|package| {
-        let file_name = archive_path.as_ref().file_name();
+    let file_name = archive_path.as_ref().file_name()?.to_string_lossy();
 
-        if let Some(file_name) = file_name {
-            let file_name = file_name.to_string_lossy();
-            package.is_match(&file_name)
-        } else {
-            false
-        }
-    })
+    name_regex
+        .captures(&file_name)
+        .map(|captures| captures[1].to_string())
 }
 
 #[cfg(test)]
 mod tests {
-    use spdx_rs::models::SPDX;
+    use spdx_rs::models::{PackageInformation, SPDX};
 
     use super::*;
+    use crate::fossology::{ArchiveMatching, ExclusionRules};
 
     #[cfg(test)]
     mod conclusions_to_spdx_expression {
@@ -527,7 +501,7 @@ mod tests {
         spdx.package_information.append(&mut packages);
 
         let closed_packages: Vec<&PackageInformation> =
-            get_packages_with_closed_license(&spdx.package_information);
+            ExclusionRules::default().excluded_packages(&spdx.package_information);
 
         assert_eq!(closed_packages.len(), 2);
         assert!(closed_packages
@@ -574,16 +548,41 @@ mod tests {
         spdx.package_information.append(&mut packages);
 
         let closed_packages: Vec<&PackageInformation> =
-            get_packages_with_closed_license(&spdx.package_information);
+            ExclusionRules::default().excluded_packages(&spdx.package_information);
 
         let nginx_path = Path::new("nginx-1.16.1-40.tar.bz2");
         let systemd_path = Path::new("systemd-1_244.5-r0.tar");
         let tzdata_path = Path::new("tzdata-2021a-r0.tar.bz2");
         let git_package_path = Path::new("git_package-gitAUTOINC+123-r0.tar.bz2");
 
-        assert!(!skip_package_upload(&nginx_path, &closed_packages));
-        assert!(skip_package_upload(&systemd_path, &closed_packages));
-        assert!(skip_package_upload(&tzdata_path, &closed_packages));
-        assert!(skip_package_upload(&git_package_path, &closed_packages));
+        let matching = ArchiveMatching::default();
+        let skip_package_upload =
+            |path: &Path| matching.skip_reason(path, &closed_packages).is_some();
+
+        assert!(!skip_package_upload(nginx_path));
+        assert!(skip_package_upload(systemd_path));
+        assert!(skip_package_upload(tzdata_path));
+        assert!(skip_package_upload(git_package_path));
+    }
+
+    #[test]
+    fn package_name_is_parsed_from_archive() {
+        assert_eq!(
+            package_name_from_archive("downloads/nginx-1.16.1-40.tar.bz2").as_deref(),
+            Some("nginx")
+        );
+        assert_eq!(
+            package_name_from_archive("systemd-1_244.5-r0.tar").as_deref(),
+            Some("systemd")
+        );
+        assert_eq!(
+            package_name_from_archive("git_package-gitAUTOINC+123-r0.tar.bz2").as_deref(),
+            Some("git_package")
+        );
+        assert_eq!(
+            package_name_from_archive("linux-yocto-v5.15-r0.tar.gz").as_deref(),
+            Some("linux-yocto")
+        );
+        assert_eq!(package_name_from_archive("README"), None);
     }
 }
@@ -0,0 +1,178 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Rules for the packages whose sources are not uploaded to or queried from Fossology.
+
+use std::{fs::read_to_string, path::Path};
+
+use anyhow::Context;
+use regex::Regex;
+use serde::{de::Error, Deserialize, Deserializer};
+use spdx_rs::models::PackageInformation;
+
+/// Rule matching packages to exclude. Each field is a regular expression searched for in the
+/// corresponding value of the package, so `^` and `$` are needed to match whole values. A package
+/// matches the rule if all of the fields of the rule match it.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExclusionRule {
+    /// Matched against each license identifier in the declared license of the package.
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub license: Option<Regex>,
+
+    /// Matched against the name of the package.
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub name: Option<Regex>,
+
+    /// Matched against the version of the package.
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub version: Option<Regex>,
+
+    /// Matched against the supplier of the package.
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub supplier: Option<Regex>,
+}
+
+/// Packages to exclude. A package is excluded if any of the rules match it.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ExclusionRules {
+    pub rules: Vec<ExclusionRule>,
+}
+
+fn deserialize_regex<'de, D>(deserializer: D) -> Result<Option<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|pattern| Regex::new(&pattern).map_err(D::Error::custom))
+        .transpose()
+}
+
+impl ExclusionRule {
+    /// Check if the rule matches the package. A rule without any fields matches nothing.
+    pub fn matches(&self, package: &PackageInformation) -> bool {
+        if self.license.is_none()
+            && self.name.is_none()
+            && self.version.is_none()
+            && self.supplier.is_none()
+        {
+            return false;
+        }
+
+        let license_matches = match &self.license {
+            Some(license) => package
+                .declared_license
+                .identifiers()
+                .iter()
+                .any(|identifier| license.is_match(identifier)),
+            None => true,
+        };
+
+        license_matches
+            && field_matches(&self.name, Some(&package.package_name))
+            && field_matches(&self.version, package.package_version.as_ref())
+            && field_matches(&self.supplier, package.package_supplier.as_ref())
+    }
+}
+
+/// Check if the value matches the pattern. Missing values don't match any pattern.
+fn field_matches(pattern: &Option<Regex>, value: Option<&String>) -> bool {
+    match pattern {
+        Some(pattern) => value.is_some_and(|value| pattern.is_match(value)),
+        None => true,
+    }
+}
+
+impl Default for ExclusionRules {
+    /// Exclude packages with CLOSED in their declared license.
+    fn default() -> Self {
+        Self {
+            rules: vec![ExclusionRule {
+                license: Some(Regex::new("CLOSED").expect("Valid regex")),
+                ..Default::default()
+            }],
+        }
+    }
+}
+
+impl ExclusionRules {
+    /// Read the rules from a YAML or JSON file.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let rules = read_to_string(&path).with_context(|| {
+            format!(
+                "Reading the exclusion rules {} failed",
+                path.as_ref().display()
+            )
+        })?;
+
+        Ok(serde_yaml::from_str(&rules)?)
+    }
+
+    /// Check if any of the rules match the package.
+    pub fn excludes(&self, package: &PackageInformation) -> bool {
+        self.rules.iter().any(|rule| rule.matches(package))
+    }
+
+    /// Get the packages matched by the rules.
+    pub fn excluded_packages<'a>(
+        &self,
+        packages: &'a [PackageInformation],
+    ) -> Vec<&'a PackageInformation> {
+        packages
+            .iter()
+            .filter(|package| self.excludes(package))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use spdx_rs::models::SpdxExpression;
+
+    use super::*;
+
+    fn package(name: &str, license: &str, supplier: Option<&str>) -> PackageInformation {
+        PackageInformation {
+            package_name: name.to_string(),
+            package_version: Some("1.0".to_string()),
+            package_supplier: supplier.map(str::to_string),
+            declared_license: SpdxExpression::parse(license).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn closed_packages_are_excluded_by_default() {
+        let rules = ExclusionRules::default();
+
+        assert!(rules.excludes(&package("firmware", "MIT AND CLOSED", None)));
+        assert!(rules.excludes(&package("firmware", "LicenseRef-CLOSED", None)));
+        assert!(!rules.excludes(&package("busybox", "GPL-2.0-only", None)));
+    }
+
+    #[test]
+    fn all_fields_of_a_rule_must_match() {
+        let rules: ExclusionRules = serde_yaml::from_str(
+            r#"
+rules:
+  - license: "^LicenseRef-Proprietary$"
+  - name: "^internal-"
+    supplier: "Organization: HH Partners"
+  - {}
+"#,
+        )
+        .unwrap();
+
+        assert!(rules.excludes(&package("tool", "MIT OR LicenseRef-Proprietary", None)));
+        assert!(!rules.excludes(&package("tool", "LicenseRef-Proprietary-2", None)));
+        assert!(rules.excludes(&package(
+            "internal-tool",
+            "MIT",
+            Some("Organization: HH Partners")
+        )));
+        assert!(!rules.excludes(&package("internal-tool", "MIT", None)));
+        assert!(!rules.excludes(&package("busybox", "GPL-2.0-only", None)));
+    }
+}
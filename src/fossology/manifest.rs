@@ -0,0 +1,295 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Manifest linking the uploaded archives to the packages in SPDX and the uploads on Fossology.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::{read_to_string, write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use spdx_rs::models::{Algorithm, Checksum, PackageInformation, SPDX};
+
+use super::{archive_matching::ArchiveMatching, status::UploadStatus};
+
+/// Archives processed by the upload subcommand.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct UploadManifest {
+    pub archives: Vec<ManifestEntry>,
+}
+
+/// Archive processed by the upload subcommand.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    /// Path to the archive.
+    pub archive: PathBuf,
+
+    /// SHA256 of the archive as lowercase hex.
+    pub sha256: String,
+
+    /// Package in SPDX the archive is the source of.
+    pub package: Option<ManifestPackage>,
+
+    /// ID of the upload on Fossology containing the archive.
+    pub upload_id: Option<i32>,
+
+    /// ID of the folder the archive was uploaded to. Not known for archives that already existed
+    /// on Fossology.
+    pub folder_id: Option<i32>,
+
+    pub status: ArchiveStatus,
+}
+
+/// Package in SPDX.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestPackage {
+    pub name: String,
+    pub version: Option<String>,
+    pub spdx_id: String,
+}
+
+/// What was done with the archive.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArchiveStatus {
+    /// Uploaded and analysis scheduled.
+    Uploaded,
+
+    /// Not uploaded because an identical archive existed on Fossology.
+    Existing,
+
+    /// Not uploaded because it's identical to another archive in the same run.
+    Duplicate,
+
+    /// Failed to upload, to unarchive or to schedule the analysis on Fossology.
+    Failed,
+}
+
+impl UploadManifest {
+    /// Read the manifest from a JSON file.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let manifest = read_to_string(&path).with_context(|| {
+            format!(
+                "Reading the upload manifest {} failed",
+                path.as_ref().display()
+            )
+        })?;
+
+        Ok(serde_json::from_str(&manifest)?)
+    }
+
+    /// Write the manifest to a JSON file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    /// IDs of the uploads of the archives that were not failed.
+    pub fn upload_ids(&self) -> BTreeSet<i32> {
+        self.archives
+            .iter()
+            .filter(|entry| entry.status != ArchiveStatus::Failed)
+            .filter_map(|entry| entry.upload_id)
+            .collect()
+    }
+
+    /// Get the entry of the archive of the package with the SPDX ID.
+    pub fn entry_for_package(&self, spdx_id: &str) -> Option<&ManifestEntry> {
+        self.archives.iter().find(|entry| {
+            entry
+                .package
+                .as_ref()
+                .is_some_and(|package| package.spdx_id == spdx_id)
+        })
+    }
+}
+
+impl ManifestPackage {
+    /// Find the package the archive is the source of with
+    /// [`ArchiveMatching::package_for_archive`].
+    pub fn for_archive(
+        archive_path: &Path,
+        packages: &[PackageInformation],
+        matching: &ArchiveMatching,
+    ) -> Option<Self> {
+        matching
+            .package_for_archive(archive_path, packages)
+            .map(|package| Self {
+                name: package.package_name.clone(),
+                version: package.package_version.clone(),
+                spdx_id: package.package_spdx_identifier.clone(),
+            })
+    }
+}
+
+/// Add the archives and the Fossology uploads from the manifest to the packages of the SPDX
+/// document, and warn about packages that have not been scanned on Fossology. The statuses of the
+/// uploads are looked up by their IDs with [`statuses_of_uploads`], and only uploads whose
+/// analysis has completed are added as provenance.
+///
+/// [`statuses_of_uploads`]: super::status::statuses_of_uploads
+pub fn add_provenance_from_manifest(
+    spdx: &mut SPDX,
+    manifest: &UploadManifest,
+    statuses: &HashMap<i32, UploadStatus>,
+) {
+    info!("Adding provenance of the packages from the upload manifest.");
+
+    for package in &mut spdx.package_information {
+        let entry = match manifest.entry_for_package(&package.package_spdx_identifier) {
+            Some(entry) => entry,
+            None => {
+                warn!(
+                    "{} is not in the upload manifest and may have no findings from Fossology.",
+                    package.package_name
+                );
+                continue;
+            }
+        };
+
+        let archive_file_name = entry
+            .archive
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy().to_string());
+
+        if package.package_file_name.is_none() {
+            package.package_file_name = archive_file_name.clone();
+        }
+
+        // The checksum is of the archive in the manifest, so it's added only if the file name of
+        // the package is the archive.
+        if package.package_file_name == archive_file_name
+            && !package
+                .package_checksum
+                .iter()
+                .any(|checksum| checksum.algorithm == Algorithm::SHA256)
+        {
+            package
+                .package_checksum
+                .push(Checksum::new(Algorithm::SHA256, &entry.sha256));
+        }
+
+        let upload_id = match (entry.status, entry.upload_id) {
+            (ArchiveStatus::Failed, _) | (_, None) => {
+                warn!(
+                    "The archive of {} has not been scanned on Fossology.",
+                    package.package_name
+                );
+                continue;
+            }
+            (_, Some(upload_id)) => upload_id,
+        };
+
+        match statuses.get(&upload_id) {
+            Some(UploadStatus::Failed) => warn!(
+                "The analysis of upload {} of {} has failed on Fossology.",
+                upload_id, package.package_name
+            ),
+            Some(UploadStatus::Pending) | None => warn!(
+                "The analysis of upload {} of {} has not finished on Fossology, findings may be \
+                 missing.",
+                upload_id, package.package_name
+            ),
+            Some(UploadStatus::Completed) => {
+                let provenance = format!("Scanned on Fossology in upload {}.", upload_id);
+                package.package_comment = Some(match package.package_comment.take() {
+                    Some(comment) if !comment.contains(&provenance) => {
+                        format!("{}\n{}", comment, provenance)
+                    }
+                    Some(comment) => comment,
+                    None => provenance,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, version: &str, spdx_id: &str) -> PackageInformation {
+        PackageInformation {
+            package_name: name.to_string(),
+            package_version: Some(version.to_string()),
+            package_spdx_identifier: spdx_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn provenance_is_added_to_packages() {
+        let mut spdx = SPDX::new("test");
+        spdx.package_information = vec![
+            package("busybox", "1.35.0", "SPDXRef-busybox"),
+            package("zlib", "1.2.11", "SPDXRef-zlib"),
+        ];
+
+        let entry = |archive: &str, upload_id: i32, packages: &[PackageInformation]| {
+            let archive = PathBuf::from(archive);
+            ManifestEntry {
+                package: ManifestPackage::for_archive(
+                    &archive,
+                    packages,
+                    &ArchiveMatching::default(),
+                ),
+                archive,
+                sha256: "ab".repeat(32),
+                upload_id: Some(upload_id),
+                folder_id: Some(3),
+                status: ArchiveStatus::Uploaded,
+            }
+        };
+        let manifest = UploadManifest {
+            archives: vec![
+                entry(
+                    "downloads/busybox-1.35.0-r0.tar.bz2",
+                    12,
+                    &spdx.package_information,
+                ),
+                entry(
+                    "downloads/zlib-1.2.11-r0.tar.gz",
+                    13,
+                    &spdx.package_information,
+                ),
+            ],
+        };
+        assert_eq!(
+            manifest.archives[0].package.as_ref().unwrap().spdx_id,
+            "SPDXRef-busybox"
+        );
+        assert_eq!(manifest.upload_ids(), BTreeSet::from([12, 13]));
+
+        spdx.package_information[1].package_file_name = Some("zlib-1.2.11.tar.xz".to_string());
+        let statuses = HashMap::from([(12, UploadStatus::Completed), (13, UploadStatus::Pending)]);
+
+        add_provenance_from_manifest(&mut spdx, &manifest, &statuses);
+        add_provenance_from_manifest(&mut spdx, &manifest, &statuses);
+
+        let busybox = &spdx.package_information[0];
+        assert_eq!(
+            busybox.package_file_name.as_deref(),
+            Some("busybox-1.35.0-r0.tar.bz2")
+        );
+        assert_eq!(busybox.package_checksum.len(), 1);
+        assert_eq!(
+            busybox.package_comment.as_deref(),
+            Some("Scanned on Fossology in upload 12.")
+        );
+
+        let zlib = &spdx.package_information[1];
+        assert_eq!(
+            zlib.package_file_name.as_deref(),
+            Some("zlib-1.2.11.tar.xz")
+        );
+        assert!(zlib.package_checksum.is_empty() && zlib.package_comment.is_none());
+    }
+}
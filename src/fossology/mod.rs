@@ -4,11 +4,27 @@
 
 //! Functionality for interacting with Fossology
 
+mod agents;
+mod archive_matching;
 mod convert_licenses;
 mod doubleopen_licenses;
+mod exclusion_rules;
+mod manifest;
 mod populate_spdx;
 mod queries;
+mod rest;
+mod status;
 mod upload_archives;
 
-pub use populate_spdx::populate_spdx_document_from_fossology;
-pub use upload_archives::upload_missing_archives_to_fossology;
+pub use agents::{Agent, AnalysisConfig, Decider};
+pub use archive_matching::ArchiveMatching;
+pub use exclusion_rules::ExclusionRules;
+pub use manifest::UploadManifest;
+pub use populate_spdx::{
+    populate_spdx_document_from_fossology, populate_spdx_stream_from_fossology,
+};
+pub use rest::FossologyRest;
+pub use status::{status_table, upload_statuses, wait_for_uploads, UploadState, UploadStatus};
+pub use upload_archives::{
+    upload_missing_archives_to_fossology, Polling, Subfolders, UploadOptions,
+};
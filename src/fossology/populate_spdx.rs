@@ -2,20 +2,30 @@
 //
 // SPDX-License-Identifier: MIT
 
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
 
 use fossology_rs::{license::get_license, upload::FilesearchResponse, Fossology};
 use log::{debug, info};
 use spdx_rs::models::{
-    Algorithm, Checksum, FileInformation, OtherLicensingInformationDetected, RelationshipType,
+    Algorithm, Checksum, OtherLicensingInformationDetected, RelationshipType, SimpleExpression,
     SpdxExpression, SPDX,
 };
 use spdx_toolkit::license_list::LicenseList;
 
-use crate::fossology::{
-    convert_licenses::update_license_to_valid_spdx,
-    doubleopen_licenses::get_packages_with_closed_license,
-    queries::filesearch_for_file_information,
+use crate::{
+    fossology::{
+        convert_licenses::update_license_to_valid_spdx,
+        exclusion_rules::ExclusionRules,
+        manifest::{add_provenance_from_manifest, UploadManifest},
+        queries::{filesearch_for_file_information, filesearch_for_sha256s},
+        status::statuses_of_uploads,
+    },
+    scanners::{update_file_from_scanner_result, ScannerResult},
+    spdx::{read_spdx_streaming, SpdxStreamWriter},
+    utilities::{create_spdx_writer, open_spdx_reader},
 };
 
 use super::{
@@ -29,6 +39,8 @@ pub fn populate_spdx_document_from_fossology(
     fossology: &Fossology,
     spdx: &mut spdx_rs::models::SPDX,
     license_list: &LicenseList,
+    manifest: Option<&UploadManifest>,
+    exclusion_rules: &ExclusionRules,
 ) -> Result<(), anyhow::Error> {
     info!("Populating SPDX from Fossology.");
 
@@ -44,7 +56,7 @@ pub fn populate_spdx_document_from_fossology(
         &sha256_values.len()
     );
 
-    let closed_recipes = get_packages_with_closed_license(&spdx.package_information);
+    let closed_recipes = exclusion_rules.excluded_packages(&spdx.package_information);
 
     let mut closed_file_hashes: HashSet<String> = HashSet::new();
 
@@ -76,12 +88,124 @@ pub fn populate_spdx_document_from_fossology(
         .cloned()
         .collect::<Vec<_>>();
 
-    debug!("Filtered source files contained by CLOSED-recipes.");
+    debug!("Filtered source files contained by excluded packages.");
 
     let responses = filesearch_for_file_information(files, fossology)?;
 
     process_fossology_responses(spdx, responses, license_list)?;
-    add_license_texts_to_spdx(spdx, license_list, fossology);
+    let licenses = spdx.get_license_ids();
+    add_license_texts_to_spdx(spdx, licenses, license_list, fossology);
+
+    if let Some(manifest) = manifest {
+        let statuses = statuses_of_uploads(fossology, manifest.upload_ids())?;
+        add_provenance_from_manifest(spdx, manifest, &statuses);
+    }
+
+    Ok(())
+}
+
+/// Populate a JSON SPDX document from Fossology like [`populate_spdx_document_from_fossology`],
+/// but read and write the files one at a time to reduce the memory usage for huge documents. The
+/// relationships and the rest of the document are kept in memory.
+///
+/// The input is read twice. The first pass only keeps the SPDX IDs and SHA256s of the files to
+/// query Fossology, the second updates the files and writes them to the output.
+pub fn populate_spdx_stream_from_fossology<P: AsRef<Path>, Q: AsRef<Path>>(
+    fossology: &Fossology,
+    input_path: P,
+    output_path: Q,
+    license_list: &LicenseList,
+    compact: bool,
+    manifest: Option<&UploadManifest>,
+    exclusion_rules: &ExclusionRules,
+) -> anyhow::Result<()> {
+    info!("Populating SPDX from Fossology one file at a time.");
+
+    let mut file_sha256s = Vec::new();
+    let mut spdx = read_spdx_streaming(open_spdx_reader(&input_path)?, |file| {
+        if let Some(sha256) = file.checksum(Algorithm::SHA256) {
+            file_sha256s.push((file.file_spdx_identifier.clone(), sha256.to_uppercase()));
+        }
+        Ok(())
+    })?;
+
+    for package in &mut spdx.package_information {
+        package.declared_license =
+            update_license_to_valid_spdx(&package.declared_license, license_list)?;
+    }
+
+    let closed_package_ids = exclusion_rules
+        .excluded_packages(&spdx.package_information)
+        .into_iter()
+        .map(|package| package.package_spdx_identifier.as_str())
+        .collect::<HashSet<_>>();
+    let closed_file_ids = spdx
+        .relationships
+        .iter()
+        .filter(|relationship| {
+            relationship.relationship_type == RelationshipType::Contains
+                && closed_package_ids.contains(relationship.spdx_element_id.as_str())
+        })
+        .map(|relationship| relationship.related_spdx_element.as_str())
+        .collect::<HashSet<_>>();
+    let closed_file_sha256s = file_sha256s
+        .iter()
+        .filter(|(id, _)| closed_file_ids.contains(id.as_str()))
+        .map(|(_, sha256)| sha256.as_str())
+        .collect::<HashSet<_>>();
+
+    let sha256s = file_sha256s
+        .iter()
+        .map(|(_, sha256)| sha256.as_str())
+        .filter(|sha256| !closed_file_sha256s.contains(sha256))
+        .collect::<HashSet<_>>();
+
+    debug!("Filtered source files contained by excluded packages.");
+
+    let responses = filesearch_for_sha256s(sha256s, fossology)?
+        .into_iter()
+        .filter_map(|response| {
+            response
+                .hash
+                .sha256
+                .clone()
+                .map(|sha256| (sha256.to_uppercase(), response))
+        })
+        .collect::<HashMap<_, _>>();
+
+    info!("Processing Fossology response");
+
+    let mut writer = SpdxStreamWriter::new(create_spdx_writer(&output_path)?, compact)?;
+    let mut licenses = HashSet::new();
+
+    read_spdx_streaming(open_spdx_reader(&input_path)?, |mut file| {
+        let response = file
+            .checksum(Algorithm::SHA256)
+            .and_then(|sha256| responses.get(&sha256.to_uppercase()));
+
+        if let Some(response) = response {
+            update_file_from_scanner_result(&mut file, response, license_list)?;
+        }
+
+        licenses.extend(
+            file.concluded_license
+                .identifiers()
+                .into_iter()
+                .filter(|license| license != "NOASSERTION" && license != "NONE"),
+        );
+
+        writer.write_file(&file)
+    })?;
+
+    add_license_texts_to_spdx(&mut spdx, licenses, license_list, fossology);
+
+    if let Some(manifest) = manifest {
+        let statuses = statuses_of_uploads(fossology, manifest.upload_ids())?;
+        add_provenance_from_manifest(&mut spdx, manifest, &statuses);
+    }
+
+    writer.finish(&spdx)?.finish()?;
+
     Ok(())
 }
 
@@ -111,7 +235,7 @@ fn process_fossology_responses(
             {
                 let response = &responses[response];
 
-                update_file_from_fossology_response(file_information, response, license_list)?;
+                update_file_from_scanner_result(file_information, response, license_list)?;
             }
         }
     }
@@ -119,10 +243,13 @@ fn process_fossology_responses(
     Ok(())
 }
 
-fn add_license_texts_to_spdx(spdx: &mut SPDX, license_list: &LicenseList, fossology: &Fossology) {
+fn add_license_texts_to_spdx(
+    spdx: &mut SPDX,
+    licenses: HashSet<String>,
+    license_list: &LicenseList,
+    fossology: &Fossology,
+) {
     // Add license texts to SPDX for licenses not on the SPDX license list.
-    let licenses = spdx.get_license_ids();
-
     for license in licenses {
         if !license_list.includes_license(&license.replace('+', "")) {
             let spdx_license = spdx
@@ -167,69 +294,63 @@ fn add_license_texts_to_spdx(spdx: &mut SPDX, license_list: &LicenseList, fossol
     }
 }
 
-/// Update [`FileInformation`] based on Fossology's [`FilesearchResponse`].
-fn update_file_from_fossology_response(
-    file: &mut FileInformation,
-    response: &FilesearchResponse,
-    license_list: &LicenseList,
-) -> anyhow::Result<()> {
-    // Add MD5 to the file in SPDX.
-    if let Some(md5) = &response.hash.md5 {
-        if file.checksum(Algorithm::MD5).is_none() {
-            file.file_checksum.push(Checksum::new(Algorithm::MD5, md5))
+impl ScannerResult for FilesearchResponse {
+    fn checksums(&self) -> Vec<Checksum> {
+        let mut checksums = Vec::new();
+
+        if let Some(md5) = &self.hash.md5 {
+            checksums.push(Checksum::new(Algorithm::MD5, md5));
         }
-    }
 
-    // Add SHA1 to the file in SPDX.
-    if let Some(sha1) = &response.hash.sha1 {
-        if file.checksum(Algorithm::SHA1).is_none() {
-            file.file_checksum
-                .push(Checksum::new(Algorithm::SHA1, sha1))
+        if let Some(sha1) = &self.hash.sha1 {
+            checksums.push(Checksum::new(Algorithm::SHA1, sha1));
         }
+
+        checksums
     }
 
-    // Add license findings to the file in SPDX.
-    if let Some(findings) = &response.findings {
-        update_file_licenses_from_fossology_response(
-            file,
-            &findings.scanner,
-            &findings.conclusion,
-            license_list,
-        )?;
-
-        if !findings.copyright.is_empty() {
-            file.copyright_text = findings.copyright.join("\n");
+    fn license_information_in_file(
+        &self,
+        license_list: &LicenseList,
+    ) -> anyhow::Result<Option<Vec<SimpleExpression>>> {
+        match &self.findings {
+            Some(findings) => Ok(Some(license_information_to_spdx_expressions(
+                &findings.scanner,
+                license_list,
+            )?)),
+            None => Ok(None),
         }
     }
 
-    Ok(())
-}
-
-/// Update the concluded license and license info in file of [`FileInformation`] based on
-/// scanner findings and conclusions from [`FilesearchResponse`].
-fn update_file_licenses_from_fossology_response(
-    file: &mut FileInformation,
-    scanner_findings: &[String],
-    conclusions: &[String],
-    license_list: &LicenseList,
-) -> anyhow::Result<()> {
-    // If scanner result is No_license_found and conlcusion is NOASSERTION
-    // conclude as NONE.
-    file.license_information_in_file =
-        license_information_to_spdx_expressions(scanner_findings, license_list)?;
-
-    if scanner_findings.len() == 1
-        && scanner_findings.contains(&"No_license_found".to_string())
-        && conclusions.len() == 1
-        && conclusions.contains(&"NOASSERTION".to_string())
-    {
-        file.concluded_license = SpdxExpression::parse("NONE").expect("Should never fail");
-    } else if !conclusions.is_empty() {
-        file.concluded_license =
-            fossology_conclusions_to_spdx_expression(conclusions, license_list);
+    fn concluded_license(&self, license_list: &LicenseList) -> Option<SpdxExpression> {
+        let findings = self.findings.as_ref()?;
+        let scanner_findings = &findings.scanner;
+        let conclusions = &findings.conclusion;
+
+        // If scanner result is No_license_found and conlcusion is NOASSERTION
+        // conclude as NONE.
+        if scanner_findings.len() == 1
+            && scanner_findings.contains(&"No_license_found".to_string())
+            && conclusions.len() == 1
+            && conclusions.contains(&"NOASSERTION".to_string())
+        {
+            Some(SpdxExpression::parse("NONE").expect("Should never fail"))
+        } else if !conclusions.is_empty() {
+            Some(fossology_conclusions_to_spdx_expression(
+                conclusions,
+                license_list,
+            ))
+        } else {
+            None
+        }
     }
 
-    Ok(())
+    fn copyrights(&self) -> Vec<String> {
+        self.findings
+            .as_ref()
+            .map(|findings| findings.copyright.clone())
+            .unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
@@ -237,6 +358,7 @@ mod tests {
     use std::fs::read_to_string;
 
     use pretty_assertions::assert_eq;
+    use spdx_rs::models::FileInformation;
 
     use crate::utilities::deserialize_spdx;
 
@@ -244,7 +366,7 @@ mod tests {
 
     #[test]
     fn correctly_process_fossology_responses() {
-        let mut spdx = deserialize_spdx("tests/data/fossology/test_spdx.json").unwrap();
+        let mut spdx = deserialize_spdx("tests/data/fossology/test_spdx.json", None).unwrap();
         let license_list = LicenseList::from_github(None).unwrap();
 
         let mut response1: Vec<FilesearchResponse> =
@@ -259,7 +381,7 @@ mod tests {
 
         process_fossology_responses(&mut spdx, response1, &license_list).unwrap();
 
-        let expected = deserialize_spdx("tests/data/fossology/expected.json").unwrap();
+        let expected = deserialize_spdx("tests/data/fossology/expected.json", None).unwrap();
 
         assert_eq!(spdx, expected);
     }
@@ -281,7 +403,7 @@ mod tests {
             serde_json::from_str(&read_to_string("tests/data/fossology/response3.json").unwrap())
                 .unwrap();
 
-        update_file_from_fossology_response(&mut file, &response, &license_list).unwrap();
+        update_file_from_scanner_result(&mut file, &response, &license_list).unwrap();
 
         assert_eq!(file, expected);
     }
@@ -4,6 +4,7 @@
 
 use std::collections::HashSet;
 
+use anyhow::Context;
 use fossology_rs::{
     upload::{filesearch, FilesearchResponse, Hash},
     Fossology,
@@ -17,29 +18,49 @@ pub fn filesearch_for_file_information(
     files: &[FileInformation],
     fossology: &Fossology,
 ) -> anyhow::Result<Vec<FilesearchResponse>> {
-    const CHUNK_SIZE: usize = 2000;
-
     let hashes = files
         .iter()
-        .filter_map(|file| {
-            file.checksum(spdx_rs::models::Algorithm::SHA256)
-                .map(Hash::from_sha256)
-        })
+        .filter_map(|file| file.checksum(spdx_rs::models::Algorithm::SHA256))
         .collect::<HashSet<_>>();
 
-    let hashes = hashes.into_iter().collect::<Vec<_>>();
+    filesearch_for_sha256s(hashes, fossology)
+}
+
+/// Query Fossology for [`FilesearchResponse`] based on unique SHA256 values.
+pub fn filesearch_for_sha256s<'a, I: IntoIterator<Item = &'a str>>(
+    sha256s: I,
+    fossology: &Fossology,
+) -> anyhow::Result<Vec<FilesearchResponse>> {
+    let hashes = sha256s
+        .into_iter()
+        .map(Hash::from_sha256)
+        .collect::<Vec<_>>();
+
+    filesearch_for_hashes(&hashes, fossology)
+}
+
+/// Query Fossology for [`FilesearchResponse`] based on unique hashes in batches.
+pub fn filesearch_for_hashes(
+    hashes: &[Hash],
+    fossology: &Fossology,
+) -> anyhow::Result<Vec<FilesearchResponse>> {
+    const CHUNK_SIZE: usize = 2000;
 
     debug!("Query fossology with {} unique hashes.", &hashes.len());
 
     let mut responses = Vec::new();
 
     for (i, batch) in hashes.chunks(CHUNK_SIZE).enumerate() {
-        info!(
-            "Querying {} / {}.",
-            ((i + 1) * CHUNK_SIZE).min(hashes.len()),
-            hashes.len()
-        );
-        responses.extend(filesearch(fossology, batch, None)?);
+        let queried = ((i + 1) * CHUNK_SIZE).min(hashes.len());
+        info!("Querying {} / {}.", queried, hashes.len());
+        let batch_responses = filesearch(fossology, batch, None).with_context(|| {
+            format!(
+                "Filesearch for hashes {} to {} failed",
+                i * CHUNK_SIZE + 1,
+                queried
+            )
+        })?;
+        responses.extend(batch_responses);
     }
 
     Ok(responses)
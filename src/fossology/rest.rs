@@ -0,0 +1,236 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Client for the endpoints of Fossology's REST API not supported by fossology-rs.
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use log::info;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use serde::{de::DeserializeOwned, Deserialize};
+
+/// Client for Fossology's REST API.
+#[derive(Debug)]
+pub struct FossologyRest {
+    /// API base uri.
+    uri: String,
+
+    /// Access token for Fossology.
+    token: String,
+
+    client: Client,
+}
+
+/// Upload on Fossology.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UploadSummary {
+    pub id: i32,
+
+    #[serde(rename = "uploadname")]
+    pub upload_name: String,
+
+    #[serde(rename = "uploaddate", default)]
+    pub upload_date: String,
+}
+
+/// Folder on Fossology.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Folder {
+    pub id: i32,
+    pub name: String,
+
+    /// ID of the parent folder, `None` for the root folder.
+    pub parent: Option<i32>,
+}
+
+/// Informational or error response of Fossology.
+#[derive(Deserialize, Debug)]
+struct Info {
+    message: String,
+}
+
+impl FossologyRest {
+    pub fn new(uri: &str, token: &str) -> anyhow::Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(600))
+            .build()?;
+
+        Ok(Self {
+            uri: uri.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            client,
+        })
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        self.client
+            .get(format!("{}/{}", self.uri, path))
+            .bearer_auth(&self.token)
+    }
+
+    fn post(&self, path: &str) -> RequestBuilder {
+        self.client
+            .post(format!("{}/{}", self.uri, path))
+            .bearer_auth(&self.token)
+    }
+
+    /// Get all folders accessible to the user.
+    pub fn folders(&self) -> anyhow::Result<Vec<Folder>> {
+        parse_response(self.get("folders").send()?)
+    }
+
+    /// Create a folder in the parent folder. Returns the ID of the new folder.
+    pub fn create_folder(&self, parent_id: i32, name: &str) -> anyhow::Result<i32> {
+        let info: Info = parse_response(
+            self.post("folders")
+                .header("parentFolder", parent_id)
+                .header("folderName", name)
+                .send()?,
+        )?;
+
+        // Fossology responds with the ID of the new folder as the message.
+        info.message.parse().map_err(|_| {
+            anyhow!(
+                "Creating folder {} failed: Fossology responded with {}",
+                name,
+                info.message
+            )
+        })
+    }
+
+    /// Get the ID of the folder at the path separated with `/` from the folders fetched with
+    /// [`FossologyRest::folders`]. The path is relative to the parent folder, or to the root
+    /// folder if no parent is given. Missing folders are created and added to the folders if
+    /// `create` is set, otherwise `None` is returned for a missing folder.
+    pub fn folder_path(
+        &self,
+        folders: &mut Vec<Folder>,
+        parent_id: Option<i32>,
+        path: &str,
+        create: bool,
+    ) -> anyhow::Result<Option<i32>> {
+        let mut folder_id = match parent_id {
+            Some(parent_id) => parent_id,
+            None => folders
+                .iter()
+                .find(|folder| folder.parent.is_none())
+                .map(|folder| folder.id)
+                .ok_or_else(|| anyhow!("Fossology has no root folder"))?,
+        };
+
+        for name in folder_path_names(path) {
+            folder_id = match child_folder(folders, folder_id, name) {
+                Some(child_id) => child_id,
+                None if create => {
+                    let child_id = self.create_folder(folder_id, name)?;
+                    info!("Created folder {} on Fossology.", name);
+                    folders.push(Folder {
+                        id: child_id,
+                        name: name.to_string(),
+                        parent: Some(folder_id),
+                    });
+                    child_id
+                }
+                None => return Ok(None),
+            };
+        }
+
+        Ok(Some(folder_id))
+    }
+
+    /// Get the uploads in the folder, excluding the uploads in its subfolders.
+    pub fn uploads_in_folder(&self, folder_id: i32) -> anyhow::Result<Vec<UploadSummary>> {
+        const PAGE_SIZE: usize = 1000;
+
+        let mut uploads = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let response = self
+                .get("uploads")
+                .query(&[
+                    ("folderId", folder_id.to_string()),
+                    ("recursive", "false".into()),
+                ])
+                .header("limit", PAGE_SIZE)
+                .header("page", page)
+                .send()?;
+
+            let total_pages = response
+                .headers()
+                .get("X-Total-Pages")
+                .and_then(|pages| pages.to_str().ok())
+                .and_then(|pages| pages.parse::<usize>().ok());
+
+            let page_uploads: Vec<UploadSummary> = parse_response(response)?;
+            let last_page = match total_pages {
+                Some(total_pages) => page >= total_pages,
+                None => page_uploads.len() < PAGE_SIZE,
+            };
+
+            uploads.extend(page_uploads);
+
+            if last_page {
+                return Ok(uploads);
+            }
+            page += 1;
+        }
+    }
+}
+
+/// Get the names of the folders in the path, ignoring empty segments.
+fn folder_path_names(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+/// Get the ID of the folder with the name in the parent folder.
+fn child_folder(folders: &[Folder], parent_id: i32, name: &str) -> Option<i32> {
+    folders
+        .iter()
+        .find(|folder| folder.parent == Some(parent_id) && folder.name == name)
+        .map(|folder| folder.id)
+}
+
+/// Parse a successful response, or convert the error message of Fossology to an error.
+fn parse_response<T: DeserializeOwned>(response: Response) -> anyhow::Result<T> {
+    let status = response.status();
+
+    if status.is_success() {
+        Ok(response.json()?)
+    } else {
+        let message = response
+            .json::<Info>()
+            .map_or_else(|_| status.to_string(), |info| info.message);
+        Err(anyhow!("Fossology responded with {}: {}", status, message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folders_are_found_by_path() {
+        let folders: Vec<Folder> = serde_json::from_str(
+            r#"[
+                {"id": 1, "name": "Software Repository", "description": "Top Folder", "parent": null},
+                {"id": 4, "name": "Product", "description": "", "parent": 1},
+                {"id": 7, "name": "Release-2026.10", "description": "", "parent": 4},
+                {"id": 9, "name": "Release-2026.10", "description": "", "parent": 1}
+            ]"#,
+        )
+        .unwrap();
+
+        let names = folder_path_names("/Product//Release-2026.10/").collect::<Vec<_>>();
+        assert_eq!(names, vec!["Product", "Release-2026.10"]);
+
+        let product = child_folder(&folders, 1, names[0]).unwrap();
+        assert_eq!(product, 4);
+        assert_eq!(child_folder(&folders, product, names[1]), Some(7));
+        assert_eq!(child_folder(&folders, 7, "Missing"), None);
+    }
+}
@@ -0,0 +1,277 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Tracking the status of the jobs of the archives uploaded to Fossology.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Write},
+    fs::{read_to_string, write},
+    path::{Path, PathBuf},
+    thread::sleep,
+    time::Instant,
+};
+
+use anyhow::Context;
+use fossology_rs::{
+    job::{get_jobs, JobStatus},
+    Fossology,
+};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use super::upload_archives::Polling;
+
+/// Archive uploaded to Fossology.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UploadedArchive {
+    /// Path to the uploaded archive.
+    pub archive: PathBuf,
+
+    /// ID of the upload on Fossology.
+    pub upload_id: i32,
+
+    /// ID of the job scheduled for the analysis of the upload.
+    pub job_id: i32,
+}
+
+/// Uploads made by the runs of the upload subcommand, persisted between runs to check the status
+/// of the analysis later. Each run adds its uploads to the uploads of the earlier runs.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct UploadState {
+    pub uploads: Vec<UploadedArchive>,
+}
+
+impl UploadState {
+    /// Read the state from a JSON file.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let state = read_to_string(&path).with_context(|| {
+            format!(
+                "Reading the upload state {} failed",
+                path.as_ref().display()
+            )
+        })?;
+
+        Ok(serde_json::from_str(&state)?)
+    }
+
+    /// Write the state to a JSON file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    /// Add the uploads to the state, replacing the earlier uploads of the same archives.
+    pub fn merge(&mut self, uploads: &[UploadedArchive]) {
+        self.uploads.retain(|tracked| {
+            !uploads.iter().any(|upload| {
+                upload.archive == tracked.archive || upload.upload_id == tracked.upload_id
+            })
+        });
+        self.uploads.extend_from_slice(uploads);
+    }
+}
+
+/// Status of all the jobs of an upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadStatus {
+    /// All jobs have completed.
+    Completed,
+
+    /// At least one of the jobs has failed.
+    Failed,
+
+    /// Jobs are queued or running.
+    Pending,
+}
+
+impl Display for UploadStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            UploadStatus::Completed => "Completed",
+            UploadStatus::Failed => "Failed",
+            UploadStatus::Pending => "Pending",
+        };
+
+        f.pad(status)
+    }
+}
+
+/// Combine the statuses of the jobs of an upload.
+fn upload_status(job_statuses: &[JobStatus]) -> UploadStatus {
+    if job_statuses.contains(&JobStatus::Failed) {
+        UploadStatus::Failed
+    } else if !job_statuses.is_empty()
+        && job_statuses
+            .iter()
+            .all(|status| status == &JobStatus::Completed)
+    {
+        UploadStatus::Completed
+    } else {
+        UploadStatus::Pending
+    }
+}
+
+/// Get the status of the analysis job of each upload.
+pub fn upload_statuses<'a>(
+    fossology: &Fossology,
+    state: &'a UploadState,
+) -> anyhow::Result<Vec<(&'a UploadedArchive, UploadStatus)>> {
+    state
+        .uploads
+        .iter()
+        .map(|upload| {
+            let jobs = get_jobs(fossology, Some(upload.upload_id), None, None, None).with_context(
+                || format!("Getting the jobs of upload {} failed", upload.upload_id),
+            )?;
+            let job_statuses = jobs
+                .into_iter()
+                .filter(|job| job.id == upload.job_id)
+                .map(|job| job.status)
+                .collect::<Vec<_>>();
+
+            Ok((upload, upload_status(&job_statuses)))
+        })
+        .collect()
+}
+
+/// Get the status of all the jobs of each upload with the ID.
+pub fn statuses_of_uploads<I>(
+    fossology: &Fossology,
+    upload_ids: I,
+) -> anyhow::Result<HashMap<i32, UploadStatus>>
+where
+    I: IntoIterator<Item = i32>,
+{
+    upload_ids
+        .into_iter()
+        .map(|upload_id| {
+            let jobs = get_jobs(fossology, Some(upload_id), None, None, None)
+                .with_context(|| format!("Getting the jobs of upload {} failed", upload_id))?;
+            let job_statuses = jobs.into_iter().map(|job| job.status).collect::<Vec<_>>();
+
+            Ok((upload_id, upload_status(&job_statuses)))
+        })
+        .collect()
+}
+
+/// Poll the status of the jobs of the uploads until none of them are pending or the timeout of the
+/// polling is reached.
+pub fn wait_for_uploads<'a>(
+    fossology: &Fossology,
+    state: &'a UploadState,
+    polling: &Polling,
+) -> anyhow::Result<Vec<(&'a UploadedArchive, UploadStatus)>> {
+    let start = Instant::now();
+    let mut interval = polling.interval;
+
+    loop {
+        let statuses = upload_statuses(fossology, state)?;
+        let pending = statuses
+            .iter()
+            .filter(|(_, status)| status == &UploadStatus::Pending)
+            .count();
+
+        if pending == 0 || start.elapsed() >= polling.timeout {
+            return Ok(statuses);
+        }
+
+        info!(
+            "Waiting for the jobs of {} of {} uploads to finish.",
+            pending,
+            statuses.len()
+        );
+        sleep(interval.min(polling.timeout.saturating_sub(start.elapsed())));
+        interval = polling.next_interval(interval);
+    }
+}
+
+/// Create a table of the statuses of the uploads.
+pub fn status_table(statuses: &[(&UploadedArchive, UploadStatus)]) -> String {
+    let mut table = String::new();
+
+    writeln!(
+        table,
+        "{:<10} {:<10} {:<10} ARCHIVE",
+        "UPLOAD", "JOB", "STATUS"
+    )
+    .expect("Writing to string");
+    for (upload, status) in statuses {
+        writeln!(
+            table,
+            "{:<10} {:<10} {:<10} {}",
+            upload.upload_id,
+            upload.job_id,
+            status,
+            upload.archive.display()
+        )
+        .expect("Writing to string");
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upload_status_combines_job_statuses() {
+        assert_eq!(
+            upload_status(&[JobStatus::Completed, JobStatus::Completed]),
+            UploadStatus::Completed
+        );
+        assert_eq!(
+            upload_status(&[JobStatus::Completed, JobStatus::Processing]),
+            UploadStatus::Pending
+        );
+        assert_eq!(
+            upload_status(&[JobStatus::Queued, JobStatus::Failed]),
+            UploadStatus::Failed
+        );
+        assert_eq!(upload_status(&[]), UploadStatus::Pending);
+    }
+
+    #[test]
+    fn status_table_lists_uploads() {
+        let upload = UploadedArchive {
+            archive: PathBuf::from("busybox-1.35.0-r0.tar.bz2"),
+            upload_id: 12,
+            job_id: 40,
+        };
+
+        assert_eq!(
+            status_table(&[(&upload, UploadStatus::Failed)]),
+            "UPLOAD     JOB        STATUS     ARCHIVE\n\
+             12         40         Failed     busybox-1.35.0-r0.tar.bz2\n"
+        );
+    }
+
+    #[test]
+    fn merged_uploads_replace_earlier_uploads_of_the_archive() {
+        let upload = |archive: &str, upload_id: i32| UploadedArchive {
+            archive: PathBuf::from(archive),
+            upload_id,
+            job_id: upload_id * 10,
+        };
+        let mut state = UploadState {
+            uploads: vec![
+                upload("zlib-1.2.11.tar.xz", 1),
+                upload("busybox-1.35.0.tar.bz2", 2),
+            ],
+        };
+
+        state.merge(&[upload("busybox-1.35.0.tar.bz2", 3)]);
+        state.merge(&[]);
+
+        assert_eq!(
+            state.uploads,
+            vec![
+                upload("zlib-1.2.11.tar.xz", 1),
+                upload("busybox-1.35.0.tar.bz2", 3)
+            ]
+        );
+    }
+}
@@ -2,116 +2,889 @@
 //
 // SPDX-License-Identifier: MIT
 
-use std::{path::Path, thread::sleep, time::Duration};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fs::read_to_string,
+    path::{Path, PathBuf},
+    thread::sleep,
+    time::{Duration, Instant},
+};
 
+use anyhow::{anyhow, Context};
 use fossology_rs::{
-    job::{schedule_analysis, ScheduleAgents},
-    upload::{filesearch, get_upload_by_id, new_upload_from_file, Hash},
+    job::{get_jobs, schedule_analysis, JobStatus},
+    upload::{get_upload_by_id, new_upload_from_file, Hash},
     Fossology, FossologyError,
 };
-use log::info;
+use log::{error, info};
+use rayon::{prelude::*, ThreadPoolBuilder};
 use spdx_rs::models::PackageInformation;
 
 use crate::{
-    fossology::doubleopen_licenses::{get_packages_with_closed_license, skip_package_upload},
-    utilities::hash256_for_path,
+    fossology::{
+        agents::AnalysisConfig,
+        archive_matching::ArchiveMatching,
+        doubleopen_licenses::package_name_from_archive,
+        exclusion_rules::ExclusionRules,
+        manifest::{ArchiveStatus, ManifestEntry, ManifestPackage, UploadManifest},
+        queries::filesearch_for_hashes,
+        rest::{FossologyRest, UploadSummary},
+        status::UploadedArchive,
+    },
+    utilities::{hashes_for_path, FileHashes},
 };
 
-pub fn upload_missing_archives_to_fossology<P: AsRef<Path>>(
+/// Options for uploading archives to Fossology.
+#[derive(Debug, Clone)]
+pub struct UploadOptions {
+    /// ID of the folder to upload the archives to.
+    pub folder: i32,
+
+    /// Only print the archives that would be uploaded and skipped.
+    pub dry_run: bool,
+
+    /// Maximum number of archives to upload at the same time.
+    pub concurrent_uploads: usize,
+
+    /// Agents to schedule for the uploads.
+    pub analysis: AnalysisConfig,
+
+    /// Polling of the uploads while they are unarchived.
+    pub unpack_polling: Polling,
+
+    /// Matching of the archives to the packages that should not be uploaded.
+    pub matching: ArchiveMatching,
+
+    /// Rules for the packages whose sources are not uploaded.
+    pub exclusion_rules: ExclusionRules,
+
+    /// Subfolders of the folder to organise the uploads into.
+    pub subfolders: Option<Subfolders>,
+}
+
+/// Grouping of the uploads into subfolders based on the packages the archives are the sources of.
+/// Archives without a package or a subfolder for the package are uploaded to the folder itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Subfolders {
+    /// Subfolder per supplier of the package.
+    Supplier,
+
+    /// Subfolder per layer of the package, with the layers by the names of the packages.
+    Layer(HashMap<String, String>),
+}
+
+impl Subfolders {
+    /// Read the layers of the packages from a YAML or JSON file mapping package names to layers.
+    pub fn read_layers<P: AsRef<Path>>(path: P) -> anyhow::Result<HashMap<String, String>> {
+        Ok(serde_yaml::from_str(&read_to_string(path)?)?)
+    }
+
+    /// Get the name of the subfolder for the package.
+    fn name(&self, package: &PackageInformation) -> Option<String> {
+        let name = match self {
+            Subfolders::Supplier => {
+                let supplier = package.package_supplier.as_deref()?;
+                if supplier == "NOASSERTION" {
+                    return None;
+                }
+                supplier
+                    .strip_prefix("Organization:")
+                    .or_else(|| supplier.strip_prefix("Person:"))
+                    .unwrap_or(supplier)
+            }
+            Subfolders::Layer(layers) => layers.get(&package.package_name)?,
+        };
+
+        // Slashes would create nested folders.
+        let name = name.trim().replace('/', "-");
+        if name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    }
+}
+
+/// Upload the archives missing from Fossology and schedule their analysis. The archives are
+/// hashed in parallel and checked with batched filesearches, and at most `concurrent_uploads`
+/// archives are uploaded at a time.
+pub fn upload_missing_archives_to_fossology<P: AsRef<Path> + Sync>(
     source_archives: Vec<P>,
     fossology: &Fossology,
-    fossolody_folder: &i32,
+    fossology_rest: &FossologyRest,
     spdx_packages: &[PackageInformation],
-    dry_run: bool,
-) -> anyhow::Result<()> {
+    options: &UploadOptions,
+) -> anyhow::Result<UploadReport> {
     info!("Uploading missing archives to Fossology.",);
 
-    let packages_to_skip = get_packages_with_closed_license(spdx_packages);
+    let packages_to_skip = options.exclusion_rules.excluded_packages(spdx_packages);
 
-    let (paths_to_skip, paths_to_upload): (Vec<_>, Vec<_>) = source_archives
-        .iter()
-        .partition(|archive| skip_package_upload(archive, &packages_to_skip));
+    let mut paths_to_skip = Vec::new();
+    let mut paths_to_upload = Vec::new();
+
+    for archive in &source_archives {
+        match options
+            .matching
+            .skip_reason(archive.as_ref(), &packages_to_skip)
+        {
+            Some(reason) => paths_to_skip.push((archive, reason)),
+            None => paths_to_upload.push(archive),
+        }
+    }
 
-    if !dry_run {
-        for path in paths_to_skip {
+    if !options.dry_run {
+        for (path, reason) in &paths_to_skip {
             let display = path.as_ref().display();
-            info!("Will not upload {} based on its license.", display);
+            info!(
+                "Will not upload {} based on the exclusion rules: {}.",
+                display, reason
+            );
         }
 
-        for file in paths_to_upload {
-            let sha256 = hash256_for_path(&file)?;
-            let input = vec![Hash::from_sha256(&sha256)];
-
-            let not_on_fossology = filesearch(fossology, &input, None).unwrap().is_empty();
-
-            if not_on_fossology {
-                let upload = new_upload_from_file(fossology, *fossolody_folder, &file)?;
-
-                let ununpack_in_progress = |fossology, upload_id| -> Result<bool, FossologyError> {
-                    get_upload_by_id(fossology, upload_id).map_or_else(
-                        |err| match err {
-                            FossologyError::Other(error_string) => {
-                                if error_string.contains("Ununpack job not started") {
-                                    Ok(true)
-                                } else {
-                                    Err(FossologyError::Other(error_string))
-                                }
-                            }
-                            err => Err(err),
-                        },
-                        |res| Ok(res.is_none()),
-                    )
-                };
-
-                while ununpack_in_progress(fossology, upload.upload_id)? {
-                    info!(
-                        "Waiting for {} to be unarchived on Fossology.",
-                        &file.as_ref().display()
-                    );
-                    sleep(Duration::from_secs(10));
-                }
+        info!("Hashing {} archives.", paths_to_upload.len());
+        let archives = paths_to_upload
+            .par_iter()
+            .map(|path| {
+                let path = path.as_ref();
+                let hashes = hashes_for_path(path)
+                    .with_context(|| format!("Hashing {} failed", path.display()))?;
+                Ok((path, hashes))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
 
-                let mut analysis_input = ScheduleAgents::default();
-                analysis_input.analysis.bucket = true;
-                analysis_input.analysis.copyright_email_author = true;
-                analysis_input.analysis.ecc = true;
-                analysis_input.analysis.keyword = true;
-                analysis_input.analysis.mime = true;
-                analysis_input.analysis.monk = true;
-                analysis_input.analysis.nomos = true;
-                analysis_input.analysis.ojo = true;
-                analysis_input.analysis.package = true;
-
-                analysis_input.decider.new_scanner = true;
-                analysis_input.decider.nomos_monk = true;
-                analysis_input.decider.ojo_decider = true;
-
-                schedule_analysis(
-                    fossology,
-                    *fossolody_folder,
-                    upload.upload_id,
-                    None,
-                    &analysis_input,
-                )?;
-            } else {
-                info!(
-                    "{} exists on Fossology, did not upload again.",
-                    &file.as_ref().display()
+        let on_fossology = archives_on_fossology(&archives, fossology)?;
+        let check = group_archives(&archives, &on_fossology);
+        let archive_folders =
+            archive_folders(&check.missing, fossology_rest, spdx_packages, options)?;
+
+        info!(
+            "{} archives already exist on Fossology, {} are duplicates, {} skipped based on \
+             their license and {} new archives will be uploaded.",
+            check.on_fossology.len(),
+            check.duplicates.len(),
+            paths_to_skip.len(),
+            check.missing.len()
+        );
+
+        // The uploads made before this run that may be reused by the new uploads.
+        let mut earlier_uploads = Vec::new();
+        if options.analysis.reuse.previous_version && options.analysis.reuse.upload.is_none() {
+            let folders = archive_folders
+                .values()
+                .copied()
+                .chain(Some(options.folder))
+                .collect::<BTreeSet<_>>();
+            for folder in folders {
+                earlier_uploads.extend(
+                    fossology_rest
+                        .uploads_in_folder(folder)
+                        .context("Getting the earlier uploads from Fossology failed")?,
                 );
             }
         }
+
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(options.concurrent_uploads.max(1))
+            .build()?;
+        let results = pool.install(|| {
+            check
+                .missing
+                .par_iter()
+                .map(|&path| {
+                    info!("Uploading {} to Fossology.", path.display());
+                    let upload = new_upload_from_file(fossology, archive_folders[path], path);
+                    (path, upload)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // Failed uploads don't stop the analysis of the archives already uploaded.
+        let mut uploads = Vec::new();
+        let mut upload_failures = Vec::new();
+        for (path, upload) in results {
+            match upload {
+                Ok(upload) => uploads.push((path, upload.upload_id)),
+                Err(err) => upload_failures.push(UploadFailure {
+                    archive: path.to_path_buf(),
+                    upload_id: None,
+                    reason: format!("upload failed: {}", err),
+                }),
+            }
+        }
+
+        let mut report = schedule_analysis_after_unpacking(
+            fossology,
+            options,
+            uploads,
+            &archive_folders,
+            &earlier_uploads,
+        );
+        report.failures.extend(upload_failures);
+        log_upload_failures(&report.failures);
+        report.manifest = upload_manifest(
+            &archives,
+            &on_fossology,
+            &report,
+            &archive_folders,
+            spdx_packages,
+            &options.matching,
+        );
+
+        Ok(report)
     } else {
         info!("DRY_RUN: Real run would UPLOAD the following packages:");
         for path in paths_to_upload {
             let display = path.as_ref().display();
-            info!("UPLOAD: {}", display);
+            match subfolder_for_archive(path.as_ref(), spdx_packages, options) {
+                Some(subfolder) => info!("UPLOAD: {} (to subfolder {})", display, subfolder),
+                None => info!("UPLOAD: {}", display),
+            }
         }
 
         info!("DRY_RUN: Real run would SKIP the following packages:");
-        for path in paths_to_skip {
+        for (path, reason) in paths_to_skip {
             let display = path.as_ref().display();
-            info!("SKIP: {}", display);
+            info!("SKIP: {} ({})", display, reason);
         }
+
+        Ok(UploadReport::default())
+    }
+}
+
+/// Get the name of the subfolder to upload the archive to, if any.
+fn subfolder_for_archive(
+    archive_path: &Path,
+    spdx_packages: &[PackageInformation],
+    options: &UploadOptions,
+) -> Option<String> {
+    let subfolders = options.subfolders.as_ref()?;
+    let package = options
+        .matching
+        .package_for_archive(archive_path, spdx_packages)?;
+
+    subfolders.name(package)
+}
+
+/// Get the IDs of the folders to upload the archives to, creating the subfolders on Fossology if
+/// they don't exist.
+fn archive_folders<'a>(
+    archives: &[&'a Path],
+    fossology_rest: &FossologyRest,
+    spdx_packages: &[PackageInformation],
+    options: &UploadOptions,
+) -> anyhow::Result<HashMap<&'a Path, i32>> {
+    let subfolders = archives
+        .iter()
+        .map(|&path| (path, subfolder_for_archive(path, spdx_packages, options)))
+        .collect::<Vec<_>>();
+
+    // The folders are fetched once and the created subfolders are added to them.
+    let mut fossology_folders = if subfolders.iter().any(|(_, subfolder)| subfolder.is_some()) {
+        fossology_rest
+            .folders()
+            .context("Getting the folders from Fossology failed")?
+    } else {
+        Vec::new()
+    };
+
+    let mut archive_folders = HashMap::new();
+
+    for (path, subfolder) in subfolders {
+        let folder_id = match subfolder {
+            Some(subfolder) => fossology_rest
+                .folder_path(
+                    &mut fossology_folders,
+                    Some(options.folder),
+                    &subfolder,
+                    true,
+                )
+                .with_context(|| format!("Creating subfolder {} failed", subfolder))?
+                .ok_or_else(|| anyhow!("Subfolder {} was not created", subfolder))?,
+            None => options.folder,
+        };
+        archive_folders.insert(path, folder_id);
+    }
+
+    Ok(archive_folders)
+}
+
+/// Archives to upload grouped by whether they need to be uploaded.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ArchiveCheck<'a> {
+    /// Archives that exist on Fossology.
+    on_fossology: Vec<&'a Path>,
+
+    /// Archives with contents identical to an earlier archive in the input.
+    duplicates: Vec<&'a Path>,
+
+    /// Archives missing from Fossology.
+    missing: Vec<&'a Path>,
+}
+
+/// Check which of the archives exist on Fossology with batched filesearches. Returns the lowercase
+/// SHA256s of the archives on Fossology with the latest upload containing the archive.
+fn archives_on_fossology(
+    archives: &[(&Path, FileHashes)],
+    fossology: &Fossology,
+) -> anyhow::Result<HashMap<String, Option<i32>>> {
+    // Fossology compares the hashes of the files in uppercase.
+    let input = archives
+        .iter()
+        .map(|(_, hashes)| Hash {
+            sha1: Some(hashes.sha1.to_uppercase()),
+            md5: Some(hashes.md5.to_uppercase()),
+            sha256: Some(hashes.sha256.to_uppercase()),
+            size: None,
+        })
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let on_fossology = filesearch_for_hashes(&input, fossology)
+        .context("Checking which archives exist on Fossology failed")?
+        .into_iter()
+        .filter_map(|response| {
+            let upload_id = response.uploads.iter().max().copied();
+            response
+                .hash
+                .sha256
+                .map(|sha256| (sha256.to_lowercase(), upload_id))
+        })
+        .collect();
+
+    Ok(on_fossology)
+}
+
+/// Group the archives based on the lowercase SHA256s of the files on Fossology.
+fn group_archives<'a>(
+    archives: &[(&'a Path, FileHashes)],
+    on_fossology: &HashMap<String, Option<i32>>,
+) -> ArchiveCheck<'a> {
+    let mut seen = HashSet::new();
+    let mut check = ArchiveCheck::default();
+
+    for (path, hashes) in archives {
+        if on_fossology.contains_key(&hashes.sha256) {
+            info!(
+                "{} exists on Fossology, did not upload again.",
+                path.display()
+            );
+            check.on_fossology.push(*path);
+        } else if !seen.insert(&hashes.sha256) {
+            info!(
+                "{} is identical to an archive already being uploaded, did not upload again.",
+                path.display()
+            );
+            check.duplicates.push(*path);
+        } else {
+            check.missing.push(*path);
+        }
+    }
+
+    check
+}
+
+/// Polling of Fossology while the uploads are unarchived or their jobs are running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Polling {
+    /// Time to wait between the first polls. Doubled after every poll up to `max_interval`.
+    pub interval: Duration,
+
+    /// Maximum time to wait between polls.
+    pub max_interval: Duration,
+
+    /// Maximum time to wait for all uploads.
+    pub timeout: Duration,
+}
+
+impl Default for Polling {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(10),
+            max_interval: Duration::from_secs(300),
+            timeout: Duration::from_secs(3600),
+        }
+    }
+}
+
+impl Polling {
+    /// Get the interval to wait after the given interval.
+    pub(crate) fn next_interval(&self, interval: Duration) -> Duration {
+        (interval * 2).min(self.max_interval)
+    }
+}
+
+/// Archive that couldn't be uploaded, unarchived or analysed on Fossology.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadFailure {
+    pub archive: PathBuf,
+
+    /// ID of the upload, `None` if the archive couldn't be uploaded.
+    pub upload_id: Option<i32>,
+
+    pub reason: String,
+}
+
+/// Results of uploading archives to Fossology.
+#[derive(Debug, Default)]
+pub struct UploadReport {
+    /// Uploads with the analysis scheduled.
+    pub uploads: Vec<UploadedArchive>,
+
+    /// Archives that failed to upload, unarchive or have their analysis scheduled, or weren't
+    /// unarchived in time.
+    pub failures: Vec<UploadFailure>,
+
+    /// All processed archives with their packages and uploads.
+    pub manifest: UploadManifest,
+}
+
+/// Status of unarchiving an upload on Fossology.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnpackStatus {
+    Unpacked,
+    InProgress,
+    Failed,
+}
+
+/// Wait for the uploads to be unarchived on Fossology and schedule the analysis of each upload as
+/// soon as it's ready. All pending uploads are polled together with exponential backoff until the
+/// timeout. Failures are recorded in the report and don't stop processing the other uploads.
+fn schedule_analysis_after_unpacking(
+    fossology: &Fossology,
+    options: &UploadOptions,
+    mut pending: Vec<(&Path, i32)>,
+    archive_folders: &HashMap<&Path, i32>,
+    earlier_uploads: &[UploadSummary],
+) -> UploadReport {
+    let polling = &options.unpack_polling;
+    let mut report = UploadReport::default();
+    let start = Instant::now();
+    let mut interval = polling.interval;
+
+    loop {
+        let mut still_pending = Vec::new();
+
+        for (path, upload_id) in pending {
+            let failure = |reason: String| UploadFailure {
+                archive: path.to_path_buf(),
+                upload_id: Some(upload_id),
+                reason,
+            };
+
+            match unpack_status(fossology, upload_id) {
+                Ok(UnpackStatus::Unpacked) => {
+                    match schedule_analysis_for_upload(
+                        fossology,
+                        options,
+                        path,
+                        upload_id,
+                        archive_folders.get(path).copied().unwrap_or(options.folder),
+                        earlier_uploads,
+                    ) {
+                        Ok(job_id) => report.uploads.push(UploadedArchive {
+                            archive: path.to_path_buf(),
+                            upload_id,
+                            job_id,
+                        }),
+                        Err(err) => report
+                            .failures
+                            .push(failure(format!("scheduling the analysis failed: {}", err))),
+                    }
+                }
+                Ok(UnpackStatus::InProgress) => still_pending.push((path, upload_id)),
+                Ok(UnpackStatus::Failed) => report
+                    .failures
+                    .push(failure("unpack job failed".to_string())),
+                Err(err) => report.failures.push(failure(format!(
+                    "checking the unpack status failed: {}",
+                    err
+                ))),
+            }
+        }
+
+        if still_pending.is_empty() {
+            break;
+        }
+
+        if start.elapsed() >= polling.timeout {
+            report
+                .failures
+                .extend(
+                    still_pending
+                        .into_iter()
+                        .map(|(path, upload_id)| UploadFailure {
+                            archive: path.to_path_buf(),
+                            upload_id: Some(upload_id),
+                            reason: format!(
+                                "not unarchived in {} seconds",
+                                polling.timeout.as_secs()
+                            ),
+                        }),
+                );
+            break;
+        }
+
+        info!(
+            "Waiting for {} uploads to be unarchived on Fossology.",
+            still_pending.len()
+        );
+        sleep(interval.min(polling.timeout.saturating_sub(start.elapsed())));
+        interval = polling.next_interval(interval);
+        pending = still_pending;
+    }
+
+    report
+}
+
+/// Log the archives that failed to upload or unarchive or whose analysis couldn't be scheduled.
+fn log_upload_failures(failures: &[UploadFailure]) {
+    if failures.is_empty() {
+        return;
+    }
+
+    error!(
+        "{} archives could not be analysed on Fossology:",
+        failures.len()
+    );
+    for failure in failures {
+        match failure.upload_id {
+            Some(upload_id) => error!(
+                "{} (upload {}): {}",
+                failure.archive.display(),
+                upload_id,
+                failure.reason
+            ),
+            None => error!("{}: {}", failure.archive.display(), failure.reason),
+        }
+    }
+}
+
+/// Schedule the analysis of the upload, reusing the conclusions of the previous version of the
+/// package if configured. Returns the ID of the scheduled job.
+fn schedule_analysis_for_upload(
+    fossology: &Fossology,
+    options: &UploadOptions,
+    path: &Path,
+    upload_id: i32,
+    folder: i32,
+    earlier_uploads: &[UploadSummary],
+) -> anyhow::Result<i32> {
+    info!("Scheduling analysis for {}.", path.display());
+
+    let analysis_input = match previous_upload(path, earlier_uploads) {
+        Some(previous) => {
+            info!(
+                "Reusing the conclusions of {} (upload {}).",
+                previous.upload_name, previous.id
+            );
+            let mut analysis_config = options.analysis.clone();
+            analysis_config.reuse.upload = Some(previous.id);
+            analysis_config.schedule_agents()
+        }
+        None => options.analysis.schedule_agents(),
+    };
+
+    let job = schedule_analysis(fossology, folder, upload_id, None, &analysis_input)?;
+
+    Ok(job.id)
+}
+
+/// Create the manifest of the archives that were uploaded or found on Fossology.
+fn upload_manifest(
+    archives: &[(&Path, FileHashes)],
+    on_fossology: &HashMap<String, Option<i32>>,
+    report: &UploadReport,
+    archive_folders: &HashMap<&Path, i32>,
+    spdx_packages: &[PackageInformation],
+    matching: &ArchiveMatching,
+) -> UploadManifest {
+    // The uploads made in this run by the SHA256 of the archive.
+    let uploads = archives
+        .iter()
+        .filter_map(|(path, hashes)| {
+            let uploaded = report
+                .uploads
+                .iter()
+                .find(|upload| upload.archive == *path)
+                .map(|upload| (Some(upload.upload_id), ArchiveStatus::Uploaded));
+            let failed = report
+                .failures
+                .iter()
+                .find(|failure| failure.archive == *path)
+                .map(|failure| (failure.upload_id, ArchiveStatus::Failed));
+
+            uploaded
+                .or(failed)
+                .map(|upload| (hashes.sha256.as_str(), (*path, upload)))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let archives = archives
+        .iter()
+        .map(|(path, hashes)| {
+            let (upload_id, folder_id, status) = match on_fossology.get(&hashes.sha256) {
+                Some(&upload_id) => (upload_id, None, ArchiveStatus::Existing),
+                None => match uploads.get(hashes.sha256.as_str()) {
+                    Some(&(uploaded_path, (upload_id, status))) if uploaded_path == *path => (
+                        upload_id,
+                        archive_folders.get(uploaded_path).copied(),
+                        status,
+                    ),
+                    Some(&(uploaded_path, (upload_id, _))) => (
+                        upload_id,
+                        archive_folders.get(uploaded_path).copied(),
+                        ArchiveStatus::Duplicate,
+                    ),
+                    None => (None, None, ArchiveStatus::Duplicate),
+                },
+            };
+
+            ManifestEntry {
+                archive: path.to_path_buf(),
+                sha256: hashes.sha256.clone(),
+                package: ManifestPackage::for_archive(path, spdx_packages, matching),
+                upload_id,
+                folder_id,
+                status,
+            }
+        })
+        .collect();
+
+    UploadManifest { archives }
+}
+
+/// Find the most recent upload of the same package as the archive, based on the package names
+/// parsed from the file names.
+fn previous_upload<'a>(
+    archive_path: &Path,
+    earlier_uploads: &'a [UploadSummary],
+) -> Option<&'a UploadSummary> {
+    let package_name = package_name_from_archive(archive_path)?;
+
+    earlier_uploads
+        .iter()
+        .filter(|upload| {
+            package_name_from_archive(&upload.upload_name).as_ref() == Some(&package_name)
+        })
+        .max_by_key(|upload| upload.id)
+}
+
+/// Check if the upload has been unarchived on Fossology. Fossology doesn't return uploads that are
+/// being unarchived, so the status of the unpack job of the upload is checked from its jobs.
+fn unpack_status(fossology: &Fossology, upload_id: i32) -> Result<UnpackStatus, FossologyError> {
+    let upload_error = match get_upload_by_id(fossology, upload_id) {
+        Ok(Some(_)) => return Ok(UnpackStatus::Unpacked),
+        Ok(None) => None,
+        Err(FossologyError::Other(message)) => Some(message),
+        Err(err) => return Err(err),
+    };
+
+    // The unpack job is created with the upload, so it's the first job of the upload.
+    let unpack_job = get_jobs(fossology, Some(upload_id), None, None, None)?
+        .into_iter()
+        .min_by_key(|job| job.id);
+
+    unpack_status_from_job(unpack_job.as_ref().map(|job| &job.status), upload_error)
+}
+
+/// Get the status of unarchiving from the status of the unpack job of an upload that is not yet
+/// available. An error getting the upload is caused by unarchiving only while the unpack job is
+/// queued or processing, otherwise the error is returned.
+fn unpack_status_from_job(
+    unpack_job: Option<&JobStatus>,
+    upload_error: Option<String>,
+) -> Result<UnpackStatus, FossologyError> {
+    match (unpack_job, upload_error) {
+        (Some(JobStatus::Failed), _) => Ok(UnpackStatus::Failed),
+        (Some(JobStatus::Queued | JobStatus::Processing), _) => Ok(UnpackStatus::InProgress),
+        (_, Some(message)) => Err(FossologyError::Other(message)),
+        (_, None) => Ok(UnpackStatus::InProgress),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hashes(sha256: &str) -> FileHashes {
+        FileHashes {
+            sha1: String::new(),
+            sha256: sha256.to_string(),
+            sha512: String::new(),
+            md5: String::new(),
+        }
+    }
+
+    #[test]
+    fn archives_are_grouped_by_existence_on_fossology() {
+        let archives = [
+            (Path::new("zlib-1.2.11.tar.xz"), hashes("aa")),
+            (Path::new("busybox-1.35.0.tar.bz2"), hashes("bb")),
+            (Path::new("busybox-copy.tar.bz2"), hashes("bb")),
+        ];
+        let on_fossology = [("aa".to_string(), Some(1))].iter().cloned().collect();
+
+        assert_eq!(
+            group_archives(&archives, &on_fossology),
+            ArchiveCheck {
+                on_fossology: vec![Path::new("zlib-1.2.11.tar.xz")],
+                duplicates: vec![Path::new("busybox-copy.tar.bz2")],
+                missing: vec![Path::new("busybox-1.35.0.tar.bz2")],
+            }
+        );
+    }
+
+    #[test]
+    fn failed_uploads_are_in_the_manifest() {
+        let archives = [
+            (Path::new("zlib-1.2.11.tar.xz"), hashes("aa")),
+            (Path::new("busybox-1.35.0.tar.bz2"), hashes("bb")),
+            (Path::new("openssl-3.0.2.tar.gz"), hashes("cc")),
+        ];
+        let report = UploadReport {
+            uploads: vec![UploadedArchive {
+                archive: PathBuf::from("zlib-1.2.11.tar.xz"),
+                upload_id: 3,
+                job_id: 8,
+            }],
+            failures: vec![
+                UploadFailure {
+                    archive: PathBuf::from("busybox-1.35.0.tar.bz2"),
+                    upload_id: None,
+                    reason: "upload failed".to_string(),
+                },
+                UploadFailure {
+                    archive: PathBuf::from("openssl-3.0.2.tar.gz"),
+                    upload_id: Some(4),
+                    reason: "unpack job failed".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+        let archive_folders = archives
+            .iter()
+            .map(|(path, _)| (*path, 2))
+            .collect::<HashMap<_, _>>();
+
+        let manifest = upload_manifest(
+            &archives,
+            &HashMap::new(),
+            &report,
+            &archive_folders,
+            &[],
+            &ArchiveMatching::default(),
+        );
+        let statuses = manifest
+            .archives
+            .iter()
+            .map(|entry| (entry.upload_id, entry.status))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            statuses,
+            vec![
+                (Some(3), ArchiveStatus::Uploaded),
+                (None, ArchiveStatus::Failed),
+                (Some(4), ArchiveStatus::Failed),
+            ]
+        );
+    }
+
+    #[test]
+    fn most_recent_upload_of_package_is_reused() {
+        let upload = |id, upload_name: &str| UploadSummary {
+            id,
+            upload_name: upload_name.to_string(),
+            upload_date: String::new(),
+        };
+        let earlier_uploads = [
+            upload(3, "busybox-1.34.0-r0.tar.bz2"),
+            upload(7, "busybox-1.35.0-r0.tar.bz2"),
+            upload(8, "busybox-extras-1.0-r0.tar.bz2"),
+            upload(5, "zlib-1.2.11-r0.tar.xz"),
+        ];
+
+        assert_eq!(
+            previous_upload(Path::new("busybox-1.36.1-r0.tar.bz2"), &earlier_uploads),
+            Some(&earlier_uploads[1])
+        );
+        assert_eq!(
+            previous_upload(Path::new("systemd-1_244.5-r0.tar"), &earlier_uploads),
+            None
+        );
+    }
+
+    #[test]
+    fn unpack_polling_backs_off_exponentially() {
+        let polling = Polling::default();
+
+        assert_eq!(
+            polling.next_interval(Duration::from_secs(10)),
+            Duration::from_secs(20)
+        );
+        assert_eq!(
+            polling.next_interval(Duration::from_secs(200)),
+            Duration::from_secs(300)
+        );
+    }
+
+    #[test]
+    fn unpack_status_follows_the_unpack_job() {
+        let error = || Some("Permission denied".to_string());
+
+        assert_eq!(
+            unpack_status_from_job(Some(&JobStatus::Processing), error()).unwrap(),
+            UnpackStatus::InProgress
+        );
+        assert_eq!(
+            unpack_status_from_job(Some(&JobStatus::Queued), None).unwrap(),
+            UnpackStatus::InProgress
+        );
+        assert_eq!(
+            unpack_status_from_job(Some(&JobStatus::Failed), None).unwrap(),
+            UnpackStatus::Failed
+        );
+        assert!(unpack_status_from_job(Some(&JobStatus::Completed), error()).is_err());
+        assert!(unpack_status_from_job(None, error()).is_err());
+        assert_eq!(
+            unpack_status_from_job(None, None).unwrap(),
+            UnpackStatus::InProgress
+        );
+    }
+
+    #[test]
+    fn subfolders_are_named_by_supplier_or_layer() {
+        let package = |name: &str, supplier: Option<&str>| PackageInformation {
+            package_name: name.to_string(),
+            package_supplier: supplier.map(str::to_string),
+            ..Default::default()
+        };
+
+        let supplier = Subfolders::Supplier;
+        assert_eq!(
+            supplier.name(&package(
+                "busybox",
+                Some("Organization: OpenEmbedded / Yocto")
+            )),
+            Some("OpenEmbedded - Yocto".to_string())
+        );
+        assert_eq!(
+            supplier.name(&package("zlib", Some("Person: Jean-loup Gailly"))),
+            Some("Jean-loup Gailly".to_string())
+        );
+        assert_eq!(supplier.name(&package("zlib", Some("NOASSERTION"))), None);
+        assert_eq!(supplier.name(&package("zlib", None)), None);
+
+        let layer = Subfolders::Layer(
+            vec![("busybox".to_string(), "meta".to_string())]
+                .into_iter()
+                .collect(),
+        );
+        assert_eq!(
+            layer.name(&package("busybox", None)),
+            Some("meta".to_string())
+        );
+        assert_eq!(layer.name(&package("zlib", None)), None);
     }
-    Ok(())
 }
@@ -3,5 +3,10 @@
 // SPDX-License-Identifier: MIT
 
 pub mod commands;
+mod convert;
+mod export;
 mod fossology;
+mod reports;
+mod scanners;
+mod spdx;
 mod utilities;
@@ -0,0 +1,330 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::{collections::BTreeSet, fmt::Write};
+
+use log::info;
+use spdx_rs::models::{FileInformation, PackageInformation, SPDX};
+
+use crate::{fossology::ExclusionRules, utilities::files_by_package};
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.5em; text-align: left; vertical-align: top; }
+th { background: #eee; }
+details { margin: 0.5em 0; }
+summary { cursor: pointer; font-weight: bold; }
+.unresolved { color: #b00; }
+.copyright { white-space: pre-wrap; font-family: monospace; }
+"#;
+
+/// Create a self-contained HTML report of the [`SPDX`] for reviewing the license conclusions.
+pub fn html_report(spdx: &SPDX, exclusion_rules: &ExclusionRules) -> anyhow::Result<String> {
+    info!("Creating HTML report.");
+
+    let files_by_package = files_by_package(spdx);
+    let excluded_packages = exclusion_rules.excluded_packages(&spdx.package_information);
+    let unresolved_files = spdx
+        .file_information
+        .iter()
+        .filter(|file| is_unresolved(file))
+        .count();
+
+    let document_name = escape_html(&spdx.document_creation_information.document_name);
+
+    let mut html = String::new();
+
+    writeln!(html, "<!DOCTYPE html>")?;
+    writeln!(html, "<html lang=\"en\">")?;
+    writeln!(html, "<head>")?;
+    writeln!(html, "<meta charset=\"utf-8\">")?;
+    writeln!(html, "<title>{}</title>", document_name)?;
+    writeln!(html, "<style>{}</style>", STYLE)?;
+    writeln!(html, "</head>")?;
+    writeln!(html, "<body>")?;
+    writeln!(html, "<h1>{}</h1>", document_name)?;
+
+    writeln!(html, "<h2>Summary</h2>")?;
+    writeln!(html, "<table>")?;
+    write_summary_row(
+        &mut html,
+        "SPDX version",
+        &spdx.document_creation_information.spdx_version,
+    )?;
+    write_summary_row(
+        &mut html,
+        "Created",
+        &spdx
+            .document_creation_information
+            .creation_info
+            .created
+            .to_rfc3339(),
+    )?;
+    write_summary_row(
+        &mut html,
+        "Packages",
+        &spdx.package_information.len().to_string(),
+    )?;
+    write_summary_row(&mut html, "Files", &spdx.file_information.len().to_string())?;
+    write_summary_row(
+        &mut html,
+        "Files with NOASSERTION",
+        &unresolved_files.to_string(),
+    )?;
+    write_summary_row(
+        &mut html,
+        "Excluded packages",
+        &excluded_packages.len().to_string(),
+    )?;
+    writeln!(html, "</table>")?;
+
+    if !excluded_packages.is_empty() {
+        writeln!(html, "<h2>Excluded packages</h2>")?;
+        writeln!(html, "<ul>")?;
+        for package in &excluded_packages {
+            writeln!(html, "<li>{}</li>", escape_html(&package_title(package)))?;
+        }
+        writeln!(html, "</ul>")?;
+    }
+
+    writeln!(html, "<h2>Packages</h2>")?;
+    writeln!(html, "<table>")?;
+    write_row(
+        &mut html,
+        "th",
+        &[
+            "Name",
+            "Version",
+            "Declared license",
+            "Concluded license",
+            "Files",
+            "Files with NOASSERTION",
+        ],
+    )?;
+    for package in &spdx.package_information {
+        let files = files_by_package
+            .get(package.package_spdx_identifier.as_str())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        write_row(
+            &mut html,
+            "td",
+            &[
+                &package.package_name,
+                package.package_version.as_deref().unwrap_or_default(),
+                &package.declared_license.to_string(),
+                &package.concluded_license.to_string(),
+                &files.len().to_string(),
+                &files
+                    .iter()
+                    .filter(|file| is_unresolved(file))
+                    .count()
+                    .to_string(),
+            ],
+        )?;
+    }
+    writeln!(html, "</table>")?;
+
+    writeln!(html, "<h2>Files by package</h2>")?;
+    for package in &spdx.package_information {
+        let files = files_by_package
+            .get(package.package_spdx_identifier.as_str())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        write_package_details(&mut html, package, files)?;
+    }
+
+    writeln!(html, "</body>")?;
+    writeln!(html, "</html>")?;
+
+    Ok(html)
+}
+
+/// Write the drill-down section with the files and copyrights of a package.
+fn write_package_details(
+    html: &mut String,
+    package: &PackageInformation,
+    files: &[&FileInformation],
+) -> anyhow::Result<()> {
+    writeln!(html, "<details>")?;
+    writeln!(
+        html,
+        "<summary>{} ({} files)</summary>",
+        escape_html(&package_title(package)),
+        files.len()
+    )?;
+
+    if !files.is_empty() {
+        writeln!(html, "<table>")?;
+        write_row(
+            html,
+            "th",
+            &["File", "Scanner findings", "Concluded license", "Copyright"],
+        )?;
+        for file in files {
+            let findings = file
+                .license_information_in_file
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            writeln!(html, "<tr>")?;
+            writeln!(html, "<td>{}</td>", escape_html(&file.file_name))?;
+            writeln!(html, "<td>{}</td>", escape_html(&findings))?;
+            if is_unresolved(file) {
+                writeln!(html, "<td class=\"unresolved\">NOASSERTION</td>")?;
+            } else {
+                writeln!(
+                    html,
+                    "<td>{}</td>",
+                    escape_html(&file.concluded_license.to_string())
+                )?;
+            }
+            writeln!(
+                html,
+                "<td class=\"copyright\">{}</td>",
+                escape_html(&file.copyright_text)
+            )?;
+            writeln!(html, "</tr>")?;
+        }
+        writeln!(html, "</table>")?;
+    }
+
+    let copyrights = package_copyrights(files);
+    if !copyrights.is_empty() {
+        writeln!(html, "<h3>Copyrights</h3>")?;
+        writeln!(html, "<ul class=\"copyright\">")?;
+        for copyright in copyrights {
+            writeln!(html, "<li>{}</li>", escape_html(copyright))?;
+        }
+        writeln!(html, "</ul>")?;
+    }
+
+    writeln!(html, "</details>")?;
+
+    Ok(())
+}
+
+/// Write a table row with the given cell element.
+fn write_row(html: &mut String, cell: &str, values: &[&str]) -> anyhow::Result<()> {
+    write!(html, "<tr>")?;
+    for value in values {
+        write!(html, "<{cell}>{}</{cell}>", escape_html(value), cell = cell)?;
+    }
+    writeln!(html, "</tr>")?;
+
+    Ok(())
+}
+
+/// Write a row of the summary table.
+fn write_summary_row(html: &mut String, label: &str, value: &str) -> anyhow::Result<()> {
+    writeln!(
+        html,
+        "<tr><th>{}</th><td>{}</td></tr>",
+        escape_html(label),
+        escape_html(value)
+    )?;
+
+    Ok(())
+}
+
+/// Unique copyright statements of the files.
+fn package_copyrights<'a>(files: &[&'a FileInformation]) -> BTreeSet<&'a str> {
+    files
+        .iter()
+        .flat_map(|file| file.copyright_text.lines())
+        .map(str::trim)
+        .filter(|copyright| !["NOASSERTION", "NONE", ""].contains(copyright))
+        .collect()
+}
+
+/// Check if the concluded license of the file is unresolved.
+fn is_unresolved(file: &FileInformation) -> bool {
+    file.concluded_license.to_string() == "NOASSERTION"
+}
+
+/// Name and version of the package.
+fn package_title(package: &PackageInformation) -> String {
+    match &package.package_version {
+        Some(version) => format!("{} {}", package.package_name, version),
+        None => package.package_name.clone(),
+    }
+}
+
+/// Escape the characters with special meaning in HTML.
+pub(crate) fn escape_html(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+
+    for character in input.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            character => escaped.push(character),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use spdx_rs::models::{Relationship, RelationshipType, SpdxExpression};
+
+    use super::*;
+
+    #[test]
+    fn escape_special_characters() {
+        assert_eq!(
+            escape_html("Copyright <jane@example.com> & \"Joe\""),
+            "Copyright &lt;jane@example.com&gt; &amp; &quot;Joe&quot;"
+        );
+    }
+
+    #[test]
+    fn report_includes_packages_and_files() {
+        let mut spdx = SPDX::new("test_spdx");
+
+        spdx.package_information = vec![
+            PackageInformation {
+                package_name: "busybox".to_string(),
+                package_spdx_identifier: "SPDXRef-busybox".to_string(),
+                package_version: Some("1.35.0".to_string()),
+                declared_license: SpdxExpression::parse("GPL-2.0-only").unwrap(),
+                ..Default::default()
+            },
+            PackageInformation {
+                package_name: "firmware".to_string(),
+                package_spdx_identifier: "SPDXRef-firmware".to_string(),
+                declared_license: SpdxExpression::parse("CLOSED").unwrap(),
+                ..Default::default()
+            },
+        ];
+        spdx.file_information = vec![FileInformation {
+            file_name: "ls.c".to_string(),
+            file_spdx_identifier: "SPDXRef-File-1".to_string(),
+            copyright_text: "Copyright <jane@example.com>".to_string(),
+            ..Default::default()
+        }];
+        spdx.relationships = vec![Relationship::new(
+            "SPDXRef-busybox",
+            "SPDXRef-File-1",
+            RelationshipType::Contains,
+            None,
+        )];
+
+        let html = html_report(&spdx, &ExclusionRules::default()).unwrap();
+
+        assert!(html.contains("<h2>Excluded packages</h2>\n<ul>\n<li>firmware</li>"));
+        assert!(html.contains("<summary>busybox 1.35.0 (1 files)</summary>"));
+        assert!(html.contains("<tr><th>Files with NOASSERTION</th><td>1</td></tr>"));
+        assert!(html.contains("<li>Copyright &lt;jane@example.com&gt;</li>"));
+    }
+}
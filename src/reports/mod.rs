@@ -0,0 +1,12 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Reports generated from populated SPDX documents.
+
+mod html;
+mod source_offer;
+
+pub(crate) use html::escape_html;
+pub use html::html_report;
+pub use source_offer::source_offer_report;
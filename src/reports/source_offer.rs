@@ -0,0 +1,294 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::{Path, PathBuf},
+};
+
+use log::{info, warn};
+use serde::Serialize;
+use spdx_rs::models::{PackageInformation, SPDX};
+
+use crate::{fossology::ArchiveMatching, utilities::files_by_package};
+
+/// License identifiers that require the corresponding source to be provided when distributing the
+/// package. The `+`, `-only` and `-or-later` variants of the licenses are matched as well.
+const SOURCE_DISTRIBUTION_LICENSES: &[&str] = &[
+    "GPL-1.0", "GPL-2.0", "GPL-3.0", "LGPL-2.0", "LGPL-2.1", "LGPL-3.0", "AGPL-1.0", "AGPL-3.0",
+    "MPL-2.0", "EPL-1.0", "EPL-2.0", "CDDL-1.0", "CDDL-1.1", "EUPL-1.1", "EUPL-1.2",
+];
+
+/// Packages of an SPDX document with source distribution obligations.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceOfferReport {
+    pub packages: Vec<SourceOfferPackage>,
+}
+
+/// Package with source distribution obligations and the source archives matched to it.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceOfferPackage {
+    pub spdx_id: String,
+    pub name: String,
+    pub version: Option<String>,
+
+    /// Licenses of the package or its files that cause the obligation.
+    pub licenses: BTreeSet<String>,
+
+    /// Source archives matched to the package.
+    pub source_archives: Vec<PathBuf>,
+}
+
+/// Create a [`SourceOfferReport`] of the packages in the [`SPDX`] whose concluded license or
+/// file-level licenses impose source distribution obligations. The declared license is used for
+/// packages without a concluded license. Source archives are matched to
+/// the packages the same way as when skipping uploads to Fossology.
+pub fn source_offer_report<P: AsRef<Path>>(
+    spdx: &SPDX,
+    source_archives: &[P],
+    matching: &ArchiveMatching,
+) -> SourceOfferReport {
+    info!("Creating source offer report.");
+
+    let files_by_package = files_by_package(spdx);
+
+    let mut packages = Vec::new();
+
+    for package in &spdx.package_information {
+        let mut licenses = source_distribution_licenses(package_licenses(package));
+
+        if let Some(files) = files_by_package.get(package.package_spdx_identifier.as_str()) {
+            for file in files {
+                licenses.extend(source_distribution_licenses(
+                    file.concluded_license.identifiers(),
+                ));
+                licenses.extend(source_distribution_licenses(
+                    file.license_information_in_file
+                        .iter()
+                        .map(ToString::to_string),
+                ));
+            }
+        }
+
+        if licenses.is_empty() {
+            continue;
+        }
+
+        let source_archives = source_archives_for_package(package, source_archives, matching);
+
+        if source_archives.is_empty() {
+            warn!(
+                "No source archive found for {} with source distribution obligations.",
+                package.package_name
+            );
+        }
+
+        packages.push(SourceOfferPackage {
+            spdx_id: package.package_spdx_identifier.clone(),
+            name: package.package_name.clone(),
+            version: package.package_version.clone(),
+            licenses,
+            source_archives,
+        });
+    }
+
+    info!(
+        "Found {} packages with source distribution obligations.",
+        packages.len()
+    );
+
+    SourceOfferReport { packages }
+}
+
+/// License identifiers of the package. The declared license is used if the package doesn't have
+/// a concluded license.
+fn package_licenses(package: &PackageInformation) -> HashSet<String> {
+    let concluded = package.concluded_license.identifiers();
+
+    if concluded
+        .iter()
+        .all(|license| license == "NOASSERTION" || license == "NONE")
+    {
+        package.declared_license.identifiers()
+    } else {
+        concluded
+    }
+}
+
+/// Filter the license identifiers that impose source distribution obligations.
+fn source_distribution_licenses<I>(identifiers: I) -> BTreeSet<String>
+where
+    I: IntoIterator<Item = String>,
+{
+    identifiers
+        .into_iter()
+        .filter(|identifier| SOURCE_DISTRIBUTION_LICENSES.contains(&base_license(identifier)))
+        .collect()
+}
+
+/// Strip the `+`, `-only` and `-or-later` suffixes from the license identifier.
+fn base_license(identifier: &str) -> &str {
+    let identifier = identifier.strip_suffix('+').unwrap_or(identifier);
+
+    identifier
+        .strip_suffix("-only")
+        .or_else(|| identifier.strip_suffix("-or-later"))
+        .unwrap_or(identifier)
+}
+
+/// Find the source archives that match the package.
+fn source_archives_for_package<P: AsRef<Path>>(
+    package: &PackageInformation,
+    source_archives: &[P],
+    matching: &ArchiveMatching,
+) -> Vec<PathBuf> {
+    source_archives
+        .iter()
+        .filter(|archive| matching.match_reason(archive.as_ref(), package).is_some())
+        .map(|archive| archive.as_ref().to_path_buf())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use spdx_rs::models::{
+        FileInformation, Relationship, RelationshipType, SimpleExpression, SpdxExpression,
+    };
+
+    use super::*;
+
+    fn spdx_with_packages() -> SPDX {
+        let mut spdx = SPDX::new("test_spdx");
+
+        spdx.package_information = vec![
+            PackageInformation {
+                package_name: "busybox".to_string(),
+                package_spdx_identifier: "SPDXRef-busybox".to_string(),
+                package_version: Some("1.35.0".to_string()),
+                concluded_license: SpdxExpression::parse("GPL-2.0-only").unwrap(),
+                ..Default::default()
+            },
+            PackageInformation {
+                package_name: "zlib".to_string(),
+                package_spdx_identifier: "SPDXRef-zlib".to_string(),
+                package_version: Some("1.2.12".to_string()),
+                concluded_license: SpdxExpression::parse("Zlib").unwrap(),
+                ..Default::default()
+            },
+            PackageInformation {
+                package_name: "firefox".to_string(),
+                package_spdx_identifier: "SPDXRef-firefox".to_string(),
+                package_version: Some("102.0".to_string()),
+                concluded_license: SpdxExpression::parse("NOASSERTION").unwrap(),
+                declared_license: SpdxExpression::parse("MPL-2.0 AND MIT").unwrap(),
+                ..Default::default()
+            },
+            PackageInformation {
+                package_name: "glibc".to_string(),
+                package_spdx_identifier: "SPDXRef-glibc".to_string(),
+                package_version: Some("2.35".to_string()),
+                concluded_license: SpdxExpression::parse("NOASSERTION").unwrap(),
+                ..Default::default()
+            },
+        ];
+
+        spdx.file_information = vec![
+            FileInformation {
+                file_name: "zlib.c".to_string(),
+                file_spdx_identifier: "SPDXRef-File-1".to_string(),
+                concluded_license: SpdxExpression::parse("Zlib").unwrap(),
+                ..Default::default()
+            },
+            FileInformation {
+                file_name: "malloc.c".to_string(),
+                file_spdx_identifier: "SPDXRef-File-2".to_string(),
+                license_information_in_file: vec![
+                    SimpleExpression::parse("LGPL-2.1-or-later").unwrap()
+                ],
+                ..Default::default()
+            },
+        ];
+
+        spdx.relationships = vec![
+            Relationship::new(
+                "SPDXRef-zlib",
+                "SPDXRef-File-1",
+                RelationshipType::Contains,
+                None,
+            ),
+            Relationship::new(
+                "SPDXRef-glibc",
+                "SPDXRef-File-2",
+                RelationshipType::Contains,
+                None,
+            ),
+        ];
+
+        spdx
+    }
+
+    #[test]
+    fn report_packages_with_source_distribution_obligations() {
+        let spdx = spdx_with_packages();
+        let archives = vec![
+            Path::new("busybox-1.35.0-r0.tar.bz2"),
+            Path::new("zlib-1.2.12-r0.tar.bz2"),
+        ];
+
+        let report = source_offer_report(&spdx, &archives, &ArchiveMatching::default());
+
+        let expected = SourceOfferReport {
+            packages: vec![
+                SourceOfferPackage {
+                    spdx_id: "SPDXRef-busybox".to_string(),
+                    name: "busybox".to_string(),
+                    version: Some("1.35.0".to_string()),
+                    licenses: BTreeSet::from(["GPL-2.0-only".to_string()]),
+                    source_archives: vec![PathBuf::from("busybox-1.35.0-r0.tar.bz2")],
+                },
+                SourceOfferPackage {
+                    spdx_id: "SPDXRef-firefox".to_string(),
+                    name: "firefox".to_string(),
+                    version: Some("102.0".to_string()),
+                    licenses: BTreeSet::from(["MPL-2.0".to_string()]),
+                    source_archives: Vec::new(),
+                },
+                SourceOfferPackage {
+                    spdx_id: "SPDXRef-glibc".to_string(),
+                    name: "glibc".to_string(),
+                    version: Some("2.35".to_string()),
+                    licenses: BTreeSet::from(["LGPL-2.1-or-later".to_string()]),
+                    source_archives: Vec::new(),
+                },
+            ],
+        };
+
+        assert_eq!(report, expected);
+    }
+
+    #[test]
+    fn only_exact_license_identifiers_impose_obligations() {
+        let identifiers = [
+            "GPL-2.0+",
+            "LGPL-3.0-or-later",
+            "AGPL-3.0-only",
+            "MPL-2.0",
+            "MPL-2.0-no-copyleft-exception",
+            "LGPLLR",
+            "MIT",
+        ];
+
+        assert_eq!(
+            source_distribution_licenses(identifiers.iter().map(ToString::to_string)),
+            BTreeSet::from([
+                "AGPL-3.0-only".to_string(),
+                "GPL-2.0+".to_string(),
+                "LGPL-3.0-or-later".to_string(),
+                "MPL-2.0".to_string(),
+            ])
+        );
+    }
+}
@@ -0,0 +1,341 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Basic scanning of local source files for when Fossology is not available.
+
+use std::{
+    fs::{self, read},
+    path::{Path, PathBuf},
+};
+
+use log::{debug, info};
+use regex::Regex;
+use spdx_rs::models::{Checksum, SimpleExpression, SpdxExpression, SPDX};
+use spdx_toolkit::license_list::LicenseList;
+
+use crate::utilities::FileHashes;
+
+use super::{
+    add_license_refs_to_spdx, license_information_from_expressions,
+    populate_spdx_from_scanner_results, ScannerResult,
+};
+
+/// Results of scanning a single local file.
+#[derive(Debug, Clone)]
+pub struct LocalScanResult {
+    pub path: PathBuf,
+    pub hashes: FileHashes,
+
+    /// License expressions of the `SPDX-License-Identifier` tags in the file.
+    pub license_expressions: Vec<String>,
+
+    /// Copyright statements in the file.
+    pub copyrights: Vec<String>,
+}
+
+impl ScannerResult for LocalScanResult {
+    fn checksums(&self) -> Vec<Checksum> {
+        self.hashes.checksums()
+    }
+
+    fn license_information_in_file(
+        &self,
+        license_list: &LicenseList,
+    ) -> anyhow::Result<Option<Vec<SimpleExpression>>> {
+        // Files without license identifiers may still have licenses that can't be detected with
+        // the tags, so the existing findings are kept.
+        if self.license_expressions.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(license_information_from_expressions(
+            &self.license_expressions,
+            license_list,
+            &self.path.to_string_lossy(),
+        )))
+    }
+
+    fn concluded_license(&self, _license_list: &LicenseList) -> Option<SpdxExpression> {
+        None
+    }
+
+    fn copyrights(&self) -> Vec<String> {
+        self.copyrights.clone()
+    }
+}
+
+/// Tag of license identifiers in files. Split so that REUSE doesn't read it as the license of this
+/// file.
+const LICENSE_IDENTIFIER_TAG: &str = concat!("SPDX-License-", "Identifier:");
+
+/// Tag of copyright statements in files.
+const COPYRIGHT_TAG: &str = concat!("SPDX-File", "CopyrightText:");
+
+/// Detects license identifiers and copyrights in the contents of files.
+struct Detector {
+    license_identifier: Regex,
+    copyright: Regex,
+    year: Regex,
+}
+
+impl Detector {
+    fn new() -> Self {
+        Self {
+            license_identifier: Regex::new(&format!(r"{}\s*(.+)", LICENSE_IDENTIFIER_TAG))
+                .expect("Regex creation to succeed"),
+            copyright: Regex::new(r"(?i)^(copyright\b|©|\(c\)\s)")
+                .expect("Regex creation to succeed"),
+            year: Regex::new(r"\b(19|20)\d{2}\b").expect("Regex creation to succeed"),
+        }
+    }
+
+    /// Get the license expressions of the `SPDX-License-Identifier` tags in the contents.
+    fn license_expressions(&self, contents: &str) -> Vec<String> {
+        let mut expressions = Vec::new();
+
+        for captures in self.license_identifier.captures_iter(contents) {
+            let expression = strip_comment_end(&captures[1]).to_string();
+            if !expression.is_empty() && !expressions.contains(&expression) {
+                expressions.push(expression);
+            }
+        }
+
+        expressions
+    }
+
+    /// Get the lines of the contents that are copyright statements.
+    fn copyrights(&self, contents: &str) -> Vec<String> {
+        let mut copyrights = Vec::new();
+
+        for line in contents.lines() {
+            let line = strip_comment_end(strip_comment_start(line));
+
+            if let Some(copyright) = line.strip_prefix(COPYRIGHT_TAG) {
+                let copyright = copyright.trim().to_string();
+                if !copyright.is_empty() && !copyrights.contains(&copyright) {
+                    copyrights.push(copyright);
+                }
+                continue;
+            }
+
+            // Require a year or a copyright sign to skip lines only mentioning copyrights.
+            let lowercase = line.to_lowercase();
+            if self.copyright.is_match(line)
+                && (self.year.is_match(line) || line.contains('©') || lowercase.contains("(c)"))
+            {
+                let copyright = line.to_string();
+                if !copyrights.contains(&copyright) {
+                    copyrights.push(copyright);
+                }
+            }
+        }
+
+        copyrights
+    }
+}
+
+/// Remove the characters starting comments in common languages from the start of the line.
+fn strip_comment_start(line: &str) -> &str {
+    let line = line.trim_start();
+    let line = line.strip_prefix("<!--").unwrap_or(line);
+
+    line.trim_start_matches(|c: char| {
+        c.is_whitespace() || matches!(c, '/' | '*' | '#' | ';' | '!' | '%' | '-' | '"' | '\'')
+    })
+}
+
+/// Remove the characters ending comments in common languages from the end of the line.
+fn strip_comment_end(line: &str) -> &str {
+    let line = line.trim_end();
+    let line = line
+        .strip_suffix("*/")
+        .or_else(|| line.strip_suffix("-->"))
+        .unwrap_or(line);
+
+    line.trim_end_matches(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | ';' | ','))
+}
+
+/// Scan the files in the directory recursively. Binary files are hashed but not searched for
+/// license identifiers or copyrights.
+pub fn scan_directory<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<LocalScanResult>> {
+    info!("Scanning files in {}.", path.as_ref().display());
+
+    let detector = Detector::new();
+    let mut results = Vec::new();
+    scan_directory_recursively(path.as_ref(), &detector, &mut results)?;
+
+    info!("Scanned {} files.", results.len());
+
+    Ok(results)
+}
+
+fn scan_directory_recursively(
+    path: &Path,
+    detector: &Detector,
+    results: &mut Vec<LocalScanResult>,
+) -> anyhow::Result<()> {
+    let mut entries = fs::read_dir(path)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+
+        // Symlinks are skipped to avoid scanning files outside the directory, and VCS metadata
+        // is not part of the sources.
+        if file_type.is_symlink() || entry.file_name() == ".git" {
+            continue;
+        }
+
+        if file_type.is_dir() {
+            scan_directory_recursively(&path, detector, results)?;
+        } else if file_type.is_file() {
+            results.push(scan_file(&path, detector)?);
+        }
+    }
+
+    Ok(())
+}
+
+fn scan_file(path: &Path, detector: &Detector) -> anyhow::Result<LocalScanResult> {
+    debug!("Scanning {}.", path.display());
+
+    let contents = read(path)?;
+    let hashes = FileHashes::from_reader(&contents[..])?;
+
+    let (license_expressions, copyrights) = if is_binary(&contents) {
+        (Vec::new(), Vec::new())
+    } else {
+        let contents = String::from_utf8_lossy(&contents);
+        (
+            detector.license_expressions(&contents),
+            detector.copyrights(&contents),
+        )
+    };
+
+    Ok(LocalScanResult {
+        path: path.to_path_buf(),
+        hashes,
+        license_expressions,
+        copyrights,
+    })
+}
+
+/// Populate the files of the SPDX document from the results of scanning local files. The files are
+/// matched by SHA256, or by SHA1 if the file has no SHA256.
+pub fn populate_spdx_from_local_scan(
+    spdx: &mut SPDX,
+    results: &[LocalScanResult],
+    license_list: &LicenseList,
+) -> anyhow::Result<()> {
+    info!("Populating SPDX from the local scan.");
+
+    let populated = populate_spdx_from_scanner_results(spdx, results, license_list)?;
+
+    info!(
+        "Populated {} of {} files from the local scan.",
+        populated,
+        spdx.file_information.len()
+    );
+
+    // The texts of the LicenseRefs are not known from the identifiers alone.
+    add_license_refs_to_spdx(spdx, |_| None);
+
+    Ok(())
+}
+
+/// Check if the contents are binary by looking for NUL bytes in the beginning.
+fn is_binary(contents: &[u8]) -> bool {
+    contents.iter().take(8000).any(|&byte| byte == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, write};
+
+    use spdx_rs::models::{Algorithm, FileInformation};
+
+    use crate::utilities::{hashes_for_path, test_license_list};
+
+    use super::*;
+
+    #[test]
+    fn detect_license_identifiers_and_copyrights() {
+        let detector = Detector::new();
+        let contents = format!(
+            "/*
+ * {copyright} 2022 HH Partners
+ * Copyright (c) 2019-2021 Example Author <author@example.com>
+ * Copyright notice and this permission notice shall be included.
+ *
+ * {license} MIT OR Apache-2.0
+ */
+# {license} MIT OR Apache-2.0
+<!-- {license} BSD-3-Clause -->
+",
+            copyright = COPYRIGHT_TAG,
+            license = LICENSE_IDENTIFIER_TAG
+        );
+
+        assert_eq!(
+            detector.license_expressions(&contents),
+            ["MIT OR Apache-2.0", "BSD-3-Clause"]
+        );
+        assert_eq!(
+            detector.copyrights(&contents),
+            [
+                "2022 HH Partners",
+                "Copyright (c) 2019-2021 Example Author <author@example.com>"
+            ]
+        );
+    }
+
+    #[test]
+    fn populate_spdx_from_local_scan_results() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let directory = temp_dir.path();
+        create_dir_all(directory.join("src")).unwrap();
+
+        let main = directory.join("src/main.c");
+        write(
+            &main,
+            format!(
+                "// Copyright (C) 2021 Example Author\n// {} GPL-2.0-only AND Proprietary\n",
+                LICENSE_IDENTIFIER_TAG
+            ),
+        )
+        .unwrap();
+        write(directory.join("data.bin"), [0, 159, 146, 150]).unwrap();
+
+        let results = scan_directory(directory).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].license_expressions.is_empty());
+
+        let mut spdx = SPDX::new("test");
+        let mut id = 1;
+        let mut file = FileInformation::new("./src/main.c", &mut id);
+        file.file_checksum.push(Checksum::new(
+            Algorithm::SHA256,
+            &hashes_for_path(&main).unwrap().sha256,
+        ));
+        spdx.file_information.push(file);
+
+        populate_spdx_from_local_scan(&mut spdx, &results, &test_license_list()).unwrap();
+
+        let file = &spdx.file_information[0];
+        assert_eq!(
+            file.license_information_in_file
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["GPL-2.0-only", "LicenseRef-Proprietary"]
+        );
+        assert_eq!(file.copyright_text, "Copyright (C) 2021 Example Author");
+        assert!(file.checksum(Algorithm::MD5).is_some());
+        assert_eq!(
+            spdx.other_licensing_information_detected[0].license_identifier,
+            "LicenseRef-Proprietary"
+        );
+    }
+}
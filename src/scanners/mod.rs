@@ -0,0 +1,203 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Scanner results used to populate the files of SPDX documents, independent of the scanner.
+
+mod local;
+mod scancode;
+
+use std::collections::{HashMap, HashSet};
+
+use log::warn;
+use spdx_rs::models::{
+    Algorithm, Checksum, FileInformation, OtherLicensingInformationDetected, SimpleExpression,
+    SpdxExpression, SPDX,
+};
+use spdx_toolkit::license_list::LicenseList;
+
+pub use local::{populate_spdx_from_local_scan, scan_directory};
+pub use scancode::{populate_spdx_from_scancode, ScanCodeOutput};
+
+/// Results of a scanner for a single file.
+pub trait ScannerResult {
+    /// Checksums of the file known by the scanner.
+    fn checksums(&self) -> Vec<Checksum>;
+
+    /// Licenses found in the file. `None` if the file has no license findings at all, in which
+    /// case the licenses of the file are not updated.
+    fn license_information_in_file(
+        &self,
+        license_list: &LicenseList,
+    ) -> anyhow::Result<Option<Vec<SimpleExpression>>>;
+
+    /// License concluded for the file, if any.
+    fn concluded_license(&self, license_list: &LicenseList) -> Option<SpdxExpression>;
+
+    /// Copyright statements found in the file.
+    fn copyrights(&self) -> Vec<String>;
+}
+
+/// Update [`FileInformation`] based on the [`ScannerResult`] of the file.
+pub fn update_file_from_scanner_result<R: ScannerResult + ?Sized>(
+    file: &mut FileInformation,
+    result: &R,
+    license_list: &LicenseList,
+) -> anyhow::Result<()> {
+    // Add checksums missing from the file in SPDX.
+    for checksum in result.checksums() {
+        if file.checksum(checksum.algorithm).is_none() {
+            file.file_checksum.push(checksum);
+        }
+    }
+
+    // Add license findings to the file in SPDX.
+    if let Some(license_information) = result.license_information_in_file(license_list)? {
+        file.license_information_in_file = license_information;
+    }
+
+    if let Some(concluded_license) = result.concluded_license(license_list) {
+        file.concluded_license = concluded_license;
+    }
+
+    let copyrights = result.copyrights();
+    if !copyrights.is_empty() {
+        file.copyright_text = copyrights.join("\n");
+    }
+
+    Ok(())
+}
+
+/// Populate the files of the SPDX document from the scanner results. The files are matched by
+/// SHA256, or by SHA1 if the SHA256 of the file is not known to the scanner. Returns the number
+/// of populated files.
+pub fn populate_spdx_from_scanner_results<'a, R, I>(
+    spdx: &mut SPDX,
+    results: I,
+    license_list: &LicenseList,
+) -> anyhow::Result<usize>
+where
+    R: ScannerResult + 'a,
+    I: IntoIterator<Item = &'a R>,
+{
+    let mut results_by_sha256 = HashMap::new();
+    let mut results_by_sha1 = HashMap::new();
+
+    for result in results {
+        for checksum in result.checksums() {
+            match checksum.algorithm {
+                Algorithm::SHA256 => {
+                    results_by_sha256.insert(checksum.value.to_lowercase(), result);
+                }
+                Algorithm::SHA1 => {
+                    results_by_sha1.insert(checksum.value.to_lowercase(), result);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut populated = 0;
+
+    for file in &mut spdx.file_information {
+        let result = file
+            .checksum(Algorithm::SHA256)
+            .and_then(|sha256| results_by_sha256.get(&sha256.to_lowercase()))
+            .or_else(|| {
+                file.checksum(Algorithm::SHA1)
+                    .and_then(|sha1| results_by_sha1.get(&sha1.to_lowercase()))
+            });
+
+        if let Some(&result) = result {
+            update_file_from_scanner_result(file, result, license_list)?;
+            populated += 1;
+        }
+    }
+
+    Ok(populated)
+}
+
+/// Convert license expressions found by a scanner to the licenses of a file. Licenses not on the
+/// license list are converted to LicenseRefs, and invalid expressions are skipped.
+fn license_information_from_expressions(
+    expressions: &[String],
+    license_list: &LicenseList,
+    path: &str,
+) -> Vec<SimpleExpression> {
+    let mut licenses: Vec<SimpleExpression> = Vec::new();
+
+    for expression in expressions {
+        let expression = match SpdxExpression::parse(expression) {
+            Ok(expression) => expression,
+            Err(err) => {
+                warn!(
+                    "Skipping invalid license expression {} in {}: {}",
+                    expression, path, err
+                );
+                continue;
+            }
+        };
+
+        for license in expression.licenses() {
+            let license = if license.license_ref
+                || license.document_ref.is_some()
+                || license_list.includes_license(&license.identifier)
+            {
+                license.clone()
+            } else {
+                SimpleExpression::new(license.identifier.clone(), None, true)
+            };
+
+            if !licenses.contains(&license) {
+                licenses.push(license);
+            }
+        }
+    }
+
+    licenses.sort_by_key(ToString::to_string);
+
+    licenses
+}
+
+/// Add the LicenseRefs found in the files but missing from the SPDX document. `license_text`
+/// gives the name and the text of the license if known.
+fn add_license_refs_to_spdx<F>(spdx: &mut SPDX, license_text: F)
+where
+    F: Fn(&str) -> Option<(String, String)>,
+{
+    let mut license_refs = spdx
+        .file_information
+        .iter()
+        .flat_map(|file| &file.license_information_in_file)
+        .filter(|license| license.license_ref && license.document_ref.is_none())
+        .map(ToString::to_string)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    license_refs.sort_unstable();
+
+    for license_ref in license_refs {
+        if spdx
+            .other_licensing_information_detected
+            .iter()
+            .any(|license| license.license_identifier == license_ref)
+        {
+            continue;
+        }
+
+        let (license_name, extracted_text) = match license_text(&license_ref) {
+            Some((name, text)) if !text.is_empty() => (name, text),
+            Some((name, _)) => (name, "NOASSERTION".to_string()),
+            None => ("NOASSERTION".to_string(), "NOASSERTION".to_string()),
+        };
+
+        spdx.other_licensing_information_detected
+            .push(OtherLicensingInformationDetected {
+                license_identifier: license_ref,
+                extracted_text,
+                license_name,
+                license_cross_reference: Vec::new(),
+                license_comment: None,
+            });
+    }
+}
@@ -0,0 +1,239 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Populating SPDX documents from the JSON output of ScanCode.
+
+use log::info;
+use serde::Deserialize;
+use spdx_rs::models::{Algorithm, Checksum, SimpleExpression, SpdxExpression, SPDX};
+use spdx_toolkit::license_list::LicenseList;
+
+use super::{
+    add_license_refs_to_spdx, license_information_from_expressions,
+    populate_spdx_from_scanner_results, ScannerResult,
+};
+
+/// JSON output of ScanCode, created with e.g. `scancode --license --copyright --info --json`.
+#[derive(Deserialize, Debug)]
+pub struct ScanCodeOutput {
+    files: Vec<ScanCodeFile>,
+
+    /// Details of the detected licenses, included since ScanCode 32.
+    #[serde(default)]
+    license_references: Vec<LicenseReference>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ScanCodeFile {
+    path: String,
+    #[serde(rename = "type")]
+    file_type: String,
+    sha1: Option<String>,
+    md5: Option<String>,
+    sha256: Option<String>,
+
+    /// License detections of ScanCode 32 and later.
+    license_detections: Option<Vec<LicenseDetection>>,
+
+    /// License detections of ScanCode before 32.
+    licenses: Option<Vec<License>>,
+
+    #[serde(default)]
+    copyrights: Vec<Copyright>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LicenseDetection {
+    license_expression_spdx: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct License {
+    key: String,
+    spdx_license_key: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Copyright {
+    #[serde(alias = "value")]
+    copyright: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct LicenseReference {
+    name: String,
+    spdx_license_key: String,
+    #[serde(default)]
+    text: String,
+}
+
+impl ScanCodeFile {
+    /// License expressions detected in the file as SPDX.
+    fn license_expressions(&self) -> Option<Vec<String>> {
+        if let Some(detections) = &self.license_detections {
+            return Some(
+                detections
+                    .iter()
+                    .filter_map(|detection| detection.license_expression_spdx.clone())
+                    .collect(),
+            );
+        }
+
+        self.licenses.as_ref().map(|licenses| {
+            licenses
+                .iter()
+                .map(|license| match &license.spdx_license_key {
+                    Some(key) if !key.is_empty() => key.clone(),
+                    _ => format!("LicenseRef-scancode-{}", license.key),
+                })
+                .collect()
+        })
+    }
+}
+
+impl ScannerResult for ScanCodeFile {
+    fn checksums(&self) -> Vec<Checksum> {
+        [
+            (Algorithm::MD5, &self.md5),
+            (Algorithm::SHA1, &self.sha1),
+            (Algorithm::SHA256, &self.sha256),
+        ]
+        .iter()
+        .filter_map(|(algorithm, value)| {
+            value.as_ref().map(|value| Checksum::new(*algorithm, value))
+        })
+        .collect()
+    }
+
+    fn license_information_in_file(
+        &self,
+        license_list: &LicenseList,
+    ) -> anyhow::Result<Option<Vec<SimpleExpression>>> {
+        Ok(self.license_expressions().map(|expressions| {
+            license_information_from_expressions(&expressions, license_list, &self.path)
+        }))
+    }
+
+    fn concluded_license(&self, _license_list: &LicenseList) -> Option<SpdxExpression> {
+        // ScanCode only detects licenses, the conclusions are left as they are.
+        None
+    }
+
+    fn copyrights(&self) -> Vec<String> {
+        self.copyrights
+            .iter()
+            .map(|copyright| copyright.copyright.clone())
+            .collect()
+    }
+}
+
+/// Populate the files of the SPDX document from the results of ScanCode. The files are matched
+/// by SHA256, or by SHA1 if the SHA256 of the file is not known to ScanCode.
+pub fn populate_spdx_from_scancode(
+    spdx: &mut SPDX,
+    scancode: &ScanCodeOutput,
+    license_list: &LicenseList,
+) -> anyhow::Result<()> {
+    info!("Populating SPDX from ScanCode.");
+
+    let results = scancode
+        .files
+        .iter()
+        .filter(|file| file.file_type == "file");
+    let populated = populate_spdx_from_scanner_results(spdx, results, license_list)?;
+
+    info!(
+        "Populated {} of {} files from ScanCode.",
+        populated,
+        spdx.file_information.len()
+    );
+
+    // Add the texts of the LicenseRefs from the license references of ScanCode.
+    add_license_refs_to_spdx(spdx, |license_ref| {
+        scancode
+            .license_references
+            .iter()
+            .find(|reference| reference.spdx_license_key == license_ref)
+            .map(|reference| (reference.name.clone(), reference.text.clone()))
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use crate::utilities::{deserialize_spdx, test_license_list};
+
+    use super::*;
+
+    #[test]
+    fn populate_spdx_from_scancode_results() {
+        let mut spdx = deserialize_spdx("tests/data/scancode/spdx.json", None).unwrap();
+        let scancode: ScanCodeOutput =
+            serde_json::from_str(&read_to_string("tests/data/scancode/scancode.json").unwrap())
+                .unwrap();
+
+        populate_spdx_from_scancode(&mut spdx, &scancode, &test_license_list()).unwrap();
+
+        // Matched by SHA256 from the output of ScanCode 32.
+        let main = &spdx.file_information[0];
+        assert_eq!(
+            main.license_information_in_file
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "Apache-2.0",
+                "LicenseRef-scancode-proprietary-license",
+                "MIT"
+            ]
+        );
+        assert_eq!(
+            main.copyright_text,
+            "Copyright (c) 2022 HH Partners\nCopyright 2021 Example Author"
+        );
+        assert_eq!(main.concluded_license.to_string(), "NOASSERTION");
+        assert_eq!(
+            main.checksum(Algorithm::MD5),
+            Some("6f1ed002ab5595859014ebf0951522d9")
+        );
+
+        // Matched by SHA1 from the output of ScanCode before 32.
+        let util = &spdx.file_information[1];
+        assert_eq!(
+            util.license_information_in_file
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "BSD-3-Clause",
+                "LicenseRef-scancode-unknown-license-reference"
+            ]
+        );
+
+        // Not in the ScanCode results.
+        assert!(spdx.file_information[2]
+            .license_information_in_file
+            .is_empty());
+
+        let proprietary = spdx
+            .other_licensing_information_detected
+            .iter()
+            .find(|license| license.license_identifier == "LicenseRef-scancode-proprietary-license")
+            .unwrap();
+        assert_eq!(proprietary.license_name, "Proprietary License");
+        assert_eq!(proprietary.extracted_text, "This is proprietary.");
+
+        let unknown = spdx
+            .other_licensing_information_detected
+            .iter()
+            .find(|license| {
+                license.license_identifier == "LicenseRef-scancode-unknown-license-reference"
+            })
+            .unwrap();
+        assert_eq!(unknown.extracted_text, "NOASSERTION");
+    }
+}
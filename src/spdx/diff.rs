@@ -0,0 +1,467 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+};
+
+use log::info;
+use serde::Serialize;
+use spdx_rs::models::{Algorithm, FileInformation, PackageInformation, SPDX};
+
+/// Differences between two SPDX documents.
+#[derive(Debug, Serialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SpdxDiff {
+    pub added_packages: Vec<PackageSummary>,
+    pub removed_packages: Vec<PackageSummary>,
+    pub version_changes: Vec<VersionChange>,
+    pub license_changes: Vec<PackageLicenseChange>,
+    pub file_changes: Vec<FileChange>,
+    pub new_license_refs: Vec<String>,
+}
+
+/// Name and version of a package.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageSummary {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+/// Package whose version changed.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionChange {
+    pub name: String,
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+}
+
+/// Package whose declared or concluded license changed.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageLicenseChange {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declared: Option<Change>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concluded: Option<Change>,
+}
+
+/// File whose concluded license or copyright changed. Files are matched by their SHA256.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChange {
+    pub file_name: String,
+    pub sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concluded: Option<Change>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copyright: Option<Change>,
+}
+
+/// Old and new value of a field.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Change {
+    pub old: String,
+    pub new: String,
+}
+
+impl Change {
+    /// Create a change if the values differ.
+    fn between(old: String, new: String) -> Option<Self> {
+        if old == new {
+            None
+        } else {
+            Some(Self { old, new })
+        }
+    }
+}
+
+/// Compare two [`SPDX`] documents.
+pub fn diff_spdx(old: &SPDX, new: &SPDX) -> SpdxDiff {
+    info!("Comparing SPDX documents.");
+
+    let mut diff = SpdxDiff::default();
+
+    let old_packages = packages_by_name(&old.package_information);
+    let new_packages = packages_by_name(&new.package_information);
+
+    let names = old_packages
+        .keys()
+        .chain(new_packages.keys())
+        .collect::<BTreeSet<_>>();
+
+    for name in names {
+        let old_packages = old_packages
+            .get(name)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let new_packages = new_packages
+            .get(name)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let PackagePairs {
+            pairs,
+            removed,
+            added,
+        } = pair_packages(old_packages, new_packages);
+
+        for (old_package, new_package) in pairs {
+            if old_package.package_version != new_package.package_version {
+                diff.version_changes.push(VersionChange {
+                    name: name.to_string(),
+                    old_version: old_package.package_version.clone(),
+                    new_version: new_package.package_version.clone(),
+                });
+            }
+
+            let declared = Change::between(
+                old_package.declared_license.to_string(),
+                new_package.declared_license.to_string(),
+            );
+            let concluded = Change::between(
+                old_package.concluded_license.to_string(),
+                new_package.concluded_license.to_string(),
+            );
+
+            if declared.is_some() || concluded.is_some() {
+                diff.license_changes.push(PackageLicenseChange {
+                    name: name.to_string(),
+                    declared,
+                    concluded,
+                });
+            }
+        }
+
+        diff.removed_packages
+            .extend(removed.into_iter().map(|package| PackageSummary {
+                name: name.to_string(),
+                version: package.package_version.clone(),
+            }));
+        diff.added_packages
+            .extend(added.into_iter().map(|package| PackageSummary {
+                name: name.to_string(),
+                version: package.package_version.clone(),
+            }));
+    }
+
+    let old_files = files_by_sha256(&old.file_information);
+
+    for new_file in &new.file_information {
+        let sha256 = match new_file.checksum(Algorithm::SHA256) {
+            Some(sha256) => sha256.to_uppercase(),
+            None => continue,
+        };
+
+        if let Some(old_file) = old_files.get(&sha256) {
+            let concluded = Change::between(
+                old_file.concluded_license.to_string(),
+                new_file.concluded_license.to_string(),
+            );
+            let copyright = Change::between(
+                old_file.copyright_text.clone(),
+                new_file.copyright_text.clone(),
+            );
+
+            if concluded.is_some() || copyright.is_some() {
+                diff.file_changes.push(FileChange {
+                    file_name: new_file.file_name.clone(),
+                    sha256: sha256.to_lowercase(),
+                    concluded,
+                    copyright,
+                });
+            }
+        }
+    }
+
+    let old_license_refs = old
+        .other_licensing_information_detected
+        .iter()
+        .map(|license| license.license_identifier.as_str())
+        .collect::<BTreeSet<_>>();
+
+    diff.new_license_refs = new
+        .other_licensing_information_detected
+        .iter()
+        .map(|license| license.license_identifier.as_str())
+        .filter(|license| !old_license_refs.contains(license))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(ToString::to_string)
+        .collect();
+
+    diff
+}
+
+/// Map packages by their name. A document can contain multiple packages with the same name.
+fn packages_by_name(packages: &[PackageInformation]) -> BTreeMap<&str, Vec<&PackageInformation>> {
+    let mut packages_by_name: BTreeMap<&str, Vec<_>> = BTreeMap::new();
+
+    for package in packages {
+        packages_by_name
+            .entry(package.package_name.as_str())
+            .or_default()
+            .push(package);
+    }
+
+    packages_by_name
+}
+
+/// Old and new packages with the same name paired with each other.
+struct PackagePairs<'a> {
+    pairs: Vec<(&'a PackageInformation, &'a PackageInformation)>,
+    removed: Vec<&'a PackageInformation>,
+    added: Vec<&'a PackageInformation>,
+}
+
+/// Pair the old and new packages with the same name. Packages are paired by version, and if a
+/// single old and a single new package remain, they are paired as a version change.
+fn pair_packages<'a>(
+    old_packages: &[&'a PackageInformation],
+    new_packages: &[&'a PackageInformation],
+) -> PackagePairs<'a> {
+    let mut pairs = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = new_packages.to_vec();
+
+    for &old_package in old_packages {
+        match added
+            .iter()
+            .position(|new_package| new_package.package_version == old_package.package_version)
+        {
+            Some(index) => pairs.push((old_package, added.remove(index))),
+            None => removed.push(old_package),
+        }
+    }
+
+    if removed.len() == 1 && added.len() == 1 {
+        pairs.push((removed.remove(0), added.remove(0)));
+    }
+
+    PackagePairs {
+        pairs,
+        removed,
+        added,
+    }
+}
+
+/// Map files by their uppercase SHA256.
+fn files_by_sha256(files: &[FileInformation]) -> HashMap<String, &FileInformation> {
+    let mut files_by_sha256 = HashMap::new();
+
+    for file in files {
+        if let Some(sha256) = file.checksum(Algorithm::SHA256) {
+            files_by_sha256.entry(sha256.to_uppercase()).or_insert(file);
+        }
+    }
+
+    files_by_sha256
+}
+
+impl Display for SpdxDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let version = |version: &Option<String>| version.clone().unwrap_or_default();
+
+        writeln!(f, "Added packages: {}", self.added_packages.len())?;
+        for package in &self.added_packages {
+            writeln!(f, "  + {} {}", package.name, version(&package.version))?;
+        }
+
+        writeln!(f, "Removed packages: {}", self.removed_packages.len())?;
+        for package in &self.removed_packages {
+            writeln!(f, "  - {} {}", package.name, version(&package.version))?;
+        }
+
+        writeln!(f, "Version changes: {}", self.version_changes.len())?;
+        for change in &self.version_changes {
+            writeln!(
+                f,
+                "  {}: {} -> {}",
+                change.name,
+                version(&change.old_version),
+                version(&change.new_version)
+            )?;
+        }
+
+        writeln!(f, "Package license changes: {}", self.license_changes.len())?;
+        for change in &self.license_changes {
+            if let Some(declared) = &change.declared {
+                writeln!(
+                    f,
+                    "  {} declared: {} -> {}",
+                    change.name, declared.old, declared.new
+                )?;
+            }
+            if let Some(concluded) = &change.concluded {
+                writeln!(
+                    f,
+                    "  {} concluded: {} -> {}",
+                    change.name, concluded.old, concluded.new
+                )?;
+            }
+        }
+
+        writeln!(f, "File changes: {}", self.file_changes.len())?;
+        for change in &self.file_changes {
+            if let Some(concluded) = &change.concluded {
+                writeln!(
+                    f,
+                    "  {} concluded: {} -> {}",
+                    change.file_name, concluded.old, concluded.new
+                )?;
+            }
+            if change.copyright.is_some() {
+                writeln!(f, "  {} copyright changed", change.file_name)?;
+            }
+        }
+
+        writeln!(f, "New LicenseRefs: {}", self.new_license_refs.len())?;
+        for license_ref in &self.new_license_refs {
+            writeln!(f, "  + {}", license_ref)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use spdx_rs::models::{Checksum, OtherLicensingInformationDetected, SpdxExpression};
+
+    use super::*;
+
+    fn package(name: &str, version: &str, declared: &str) -> PackageInformation {
+        PackageInformation {
+            package_name: name.to_string(),
+            package_version: Some(version.to_string()),
+            declared_license: SpdxExpression::parse(declared).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    fn file(name: &str, sha256: &str, concluded: &str) -> FileInformation {
+        FileInformation {
+            file_name: name.to_string(),
+            file_checksum: vec![Checksum::new(Algorithm::SHA256, sha256)],
+            concluded_license: SpdxExpression::parse(concluded).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn differences_are_found() {
+        let mut old = SPDX::new("old");
+        old.package_information = vec![
+            package("busybox", "1.35.0", "GPL-2.0-only"),
+            package("zlib", "1.2.11", "Zlib"),
+            package("openssl", "1.1.1", "OpenSSL"),
+        ];
+        old.file_information = vec![
+            file("zlib.c", "aaa", "Zlib"),
+            file("ls.c", "bbb", "NOASSERTION"),
+        ];
+
+        let mut new = SPDX::new("new");
+        new.package_information = vec![
+            package("busybox", "1.35.0", "GPL-2.0-only"),
+            package("zlib", "1.2.12", "Zlib"),
+            package("openssl", "3.0.5", "Apache-2.0"),
+            package("curl", "7.84.0", "curl"),
+        ];
+        new.file_information = vec![
+            file("zlib.c", "aaa", "Zlib"),
+            file("ls.c", "BBB", "GPL-2.0-only"),
+        ];
+        new.other_licensing_information_detected = vec![OtherLicensingInformationDetected {
+            license_identifier: "LicenseRef-Custom".to_string(),
+            ..Default::default()
+        }];
+
+        let diff = diff_spdx(&old, &new);
+
+        let expected = SpdxDiff {
+            added_packages: vec![PackageSummary {
+                name: "curl".to_string(),
+                version: Some("7.84.0".to_string()),
+            }],
+            removed_packages: Vec::new(),
+            version_changes: vec![
+                VersionChange {
+                    name: "openssl".to_string(),
+                    old_version: Some("1.1.1".to_string()),
+                    new_version: Some("3.0.5".to_string()),
+                },
+                VersionChange {
+                    name: "zlib".to_string(),
+                    old_version: Some("1.2.11".to_string()),
+                    new_version: Some("1.2.12".to_string()),
+                },
+            ],
+            license_changes: vec![PackageLicenseChange {
+                name: "openssl".to_string(),
+                declared: Some(Change {
+                    old: "OpenSSL".to_string(),
+                    new: "Apache-2.0".to_string(),
+                }),
+                concluded: None,
+            }],
+            file_changes: vec![FileChange {
+                file_name: "ls.c".to_string(),
+                sha256: "bbb".to_string(),
+                concluded: Some(Change {
+                    old: "NOASSERTION".to_string(),
+                    new: "GPL-2.0-only".to_string(),
+                }),
+                copyright: None,
+            }],
+            new_license_refs: vec!["LicenseRef-Custom".to_string()],
+        };
+
+        assert_eq!(diff, expected);
+    }
+
+    #[test]
+    fn packages_with_the_same_name_are_matched_by_version() {
+        let mut old = SPDX::new("old");
+        old.package_information = vec![
+            package("python3", "3.9.0", "PSF-2.0"),
+            package("python3", "2.7.18", "PSF-2.0"),
+            package("zlib", "1.2.11", "Zlib"),
+        ];
+
+        let mut new = SPDX::new("new");
+        new.package_information = vec![
+            package("python3", "2.7.18", "PSF-2.0"),
+            package("python3", "3.10.4", "PSF-2.0"),
+            package("python3", "3.11.0", "PSF-2.0"),
+            package("zlib", "1.2.11", "Zlib"),
+        ];
+
+        let diff = diff_spdx(&old, &new);
+
+        let expected = SpdxDiff {
+            added_packages: vec![
+                PackageSummary {
+                    name: "python3".to_string(),
+                    version: Some("3.10.4".to_string()),
+                },
+                PackageSummary {
+                    name: "python3".to_string(),
+                    version: Some("3.11.0".to_string()),
+                },
+            ],
+            removed_packages: vec![PackageSummary {
+                name: "python3".to_string(),
+                version: Some("3.9.0".to_string()),
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(diff, expected);
+    }
+}
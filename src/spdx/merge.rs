@@ -0,0 +1,596 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::collections::{HashMap, HashSet};
+
+use log::info;
+use regex::{Captures, Regex};
+use spdx_rs::models::{
+    Algorithm, FileInformation, OtherLicensingInformationDetected, PackageInformation,
+    Relationship, SimpleExpression, SpdxExpression, SPDX,
+};
+
+/// Merges multiple [`SPDX`] documents into one.
+struct Merger {
+    merged: SPDX,
+
+    /// SPDX IDs used in the merged document.
+    used_ids: HashSet<String>,
+
+    /// SPDX IDs of the packages in the merged document by name and version.
+    packages: HashMap<(String, Option<String>), String>,
+
+    /// SPDX IDs of the files in the merged document by name and checksum.
+    files: HashMap<(String, String), String>,
+
+    relationships: HashSet<Relationship>,
+}
+
+impl Merger {
+    fn new(name: &str) -> Self {
+        let mut merged = SPDX::new(name);
+        merged.document_creation_information.creation_info.creators = vec![format!(
+            "Tool: {}-{}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        )];
+
+        let used_ids =
+            HashSet::from([merged.document_creation_information.spdx_identifier.clone()]);
+
+        Self {
+            merged,
+            used_ids,
+            packages: HashMap::new(),
+            files: HashMap::new(),
+            relationships: HashSet::new(),
+        }
+    }
+
+    /// Create an SPDX ID that is not yet used in the merged document.
+    fn unique_id(&mut self, id: &str, document_index: usize) -> String {
+        let mut unique_id = id.to_string();
+        let mut counter = document_index;
+
+        while self.used_ids.contains(&unique_id) {
+            unique_id = format!("{}-{}", id, counter);
+            counter += 1;
+        }
+
+        self.used_ids.insert(unique_id.clone());
+        unique_id
+    }
+
+    /// Add the external document references of a document to the merged document. References to
+    /// already added documents are reused, and references whose ID is already used for another
+    /// document are renamed. Returns the renamed references.
+    fn add_external_document_references(
+        &mut self,
+        spdx: &SPDX,
+        document_index: usize,
+    ) -> HashMap<String, String> {
+        let mut renamed = HashMap::new();
+        let references = &mut self
+            .merged
+            .document_creation_information
+            .external_document_references;
+
+        for reference in &spdx
+            .document_creation_information
+            .external_document_references
+        {
+            if let Some(existing) = references.iter().find(|existing| {
+                existing.spdx_document_uri == reference.spdx_document_uri
+                    && existing.checksum == reference.checksum
+            }) {
+                if existing.id_string != reference.id_string {
+                    renamed.insert(reference.id_string.clone(), existing.id_string.clone());
+                }
+                continue;
+            }
+
+            let mut id_string = reference.id_string.clone();
+            let mut counter = document_index;
+            while references
+                .iter()
+                .any(|existing| existing.id_string == id_string)
+            {
+                id_string = format!("{}-{}", reference.id_string, counter);
+                counter += 1;
+            }
+
+            if id_string != reference.id_string {
+                info!(
+                    "{} refers to different documents, renaming it to {}.",
+                    reference.id_string, id_string
+                );
+                renamed.insert(reference.id_string.clone(), id_string.clone());
+            }
+
+            let mut reference = reference.clone();
+            reference.id_string = id_string;
+            references.push(reference);
+        }
+
+        renamed
+    }
+
+    /// Add the extracted licenses of a document to the merged document. Licenses already in the
+    /// merged document with the same text are reused, and licenses whose ID is already used for
+    /// another text are renamed. Returns the renamed licenses.
+    fn add_extracted_licenses(
+        &mut self,
+        licenses: Vec<OtherLicensingInformationDetected>,
+        document_index: usize,
+    ) -> HashMap<String, String> {
+        let mut renamed = HashMap::new();
+        let merged_licenses = &mut self.merged.other_licensing_information_detected;
+
+        for mut license in licenses {
+            let mut license_identifier = license.license_identifier.clone();
+            let mut counter = document_index;
+
+            let reused = loop {
+                match merged_licenses
+                    .iter()
+                    .find(|existing| existing.license_identifier == license_identifier)
+                {
+                    Some(existing) if existing.extracted_text == license.extracted_text => {
+                        break true
+                    }
+                    Some(_) => {
+                        license_identifier = format!("{}-{}", license.license_identifier, counter);
+                        counter += 1;
+                    }
+                    None => break false,
+                }
+            };
+
+            if license_identifier != license.license_identifier {
+                info!(
+                    "{} has different texts in the documents, renaming it to {}.",
+                    license.license_identifier, license_identifier
+                );
+                renamed.insert(
+                    license.license_identifier.clone(),
+                    license_identifier.clone(),
+                );
+            }
+
+            if !reused {
+                license.license_identifier = license_identifier;
+                merged_licenses.push(license);
+            }
+        }
+
+        renamed
+    }
+
+    /// Add a document to the merged document. IDs of the document's elements are rewritten to
+    /// avoid collisions and to point to the already merged duplicates.
+    fn add(&mut self, mut spdx: SPDX, document_index: usize) {
+        let document_refs = self.add_external_document_references(&spdx, document_index);
+        let license_refs = self.add_extracted_licenses(
+            std::mem::take(&mut spdx.other_licensing_information_detected),
+            document_index,
+        );
+        let mut ids = Rewrites::new(document_refs, license_refs);
+        ids.insert(
+            spdx.document_creation_information.spdx_identifier.clone(),
+            self.merged
+                .document_creation_information
+                .spdx_identifier
+                .clone(),
+        );
+
+        for file in spdx.file_information {
+            let key = file_checksum(&file).map(|checksum| (file.file_name.clone(), checksum));
+
+            if let Some(existing_id) = key.as_ref().and_then(|key| self.files.get(key)) {
+                ids.insert(file.file_spdx_identifier.clone(), existing_id.clone());
+                continue;
+            }
+
+            let id = self.unique_id(&file.file_spdx_identifier, document_index);
+            ids.insert(file.file_spdx_identifier.clone(), id.clone());
+            if let Some(key) = key {
+                self.files.insert(key, id.clone());
+            }
+            self.merged.file_information.push(FileInformation {
+                file_spdx_identifier: id,
+                concluded_license: ids.expression(file.concluded_license),
+                license_information_in_file: file
+                    .license_information_in_file
+                    .into_iter()
+                    .map(|license| ids.simple_expression(license))
+                    .collect(),
+                ..file
+            });
+        }
+
+        for package in spdx.package_information {
+            let key = (
+                package.package_name.clone(),
+                package.package_version.clone(),
+            );
+
+            if let Some(existing_id) = self.packages.get(&key) {
+                ids.insert(package.package_spdx_identifier.clone(), existing_id.clone());
+                continue;
+            }
+
+            let id = self.unique_id(&package.package_spdx_identifier, document_index);
+            ids.insert(package.package_spdx_identifier.clone(), id.clone());
+            self.packages.insert(key, id.clone());
+            let files = package.files.iter().map(|file| ids.id(file)).collect();
+            self.merged.package_information.push(PackageInformation {
+                package_spdx_identifier: id,
+                files,
+                concluded_license: ids.expression(package.concluded_license),
+                declared_license: ids.expression(package.declared_license),
+                all_licenses_information_from_files: package
+                    .all_licenses_information_from_files
+                    .iter()
+                    .map(|license| ids.references(license))
+                    .collect(),
+                ..package
+            });
+        }
+
+        for mut snippet in spdx.snippet_information {
+            let id = self.unique_id(&snippet.snippet_spdx_identifier, document_index);
+            ids.insert(snippet.snippet_spdx_identifier.clone(), id.clone());
+            snippet.snippet_spdx_identifier = id;
+            snippet.snippet_from_file_spdx_identifier =
+                ids.id(&snippet.snippet_from_file_spdx_identifier);
+            snippet.snippet_concluded_license = ids.expression(snippet.snippet_concluded_license);
+            snippet.license_information_in_snippet = snippet
+                .license_information_in_snippet
+                .iter()
+                .map(|license| ids.references(license))
+                .collect();
+            self.merged.snippet_information.push(snippet);
+        }
+
+        for mut relationship in spdx.relationships {
+            relationship.spdx_element_id = ids.id(&relationship.spdx_element_id);
+            relationship.related_spdx_element = ids.id(&relationship.related_spdx_element);
+
+            if self.relationships.insert(relationship.clone()) {
+                self.merged.relationships.push(relationship);
+            }
+        }
+
+        for mut annotation in spdx.annotations {
+            annotation.spdx_identifier_reference =
+                annotation.spdx_identifier_reference.map(|id| ids.id(&id));
+            self.merged.annotations.push(annotation);
+        }
+
+        let document_information = &mut self.merged.document_creation_information;
+
+        for described in spdx.document_creation_information.document_describes {
+            let described = ids.id(&described);
+            if !document_information.document_describes.contains(&described) {
+                document_information.document_describes.push(described);
+            }
+        }
+    }
+}
+
+/// Merge [`SPDX`] documents into a single document. Packages are deduplicated by name and
+/// version and files by name and checksum. SPDX IDs are rewritten to avoid collisions. The merged
+/// document uses the highest SPDX version of the documents.
+pub fn merge_spdx(documents: Vec<SPDX>, name: &str) -> SPDX {
+    info!("Merging {} SPDX documents.", documents.len());
+
+    let mut merger = Merger::new(name);
+
+    if let Some(version) = documents
+        .iter()
+        .map(|spdx| &spdx.document_creation_information.spdx_version)
+        .max_by_key(|version| spdx_version_number(version))
+    {
+        merger.merged.document_creation_information.spdx_version = version.clone();
+    }
+
+    for (index, spdx) in documents.into_iter().enumerate() {
+        merger.add(spdx, index + 1);
+    }
+
+    info!(
+        "Merged SPDX has {} packages and {} files.",
+        merger.merged.package_information.len(),
+        merger.merged.file_information.len()
+    );
+
+    merger.merged
+}
+
+/// Parse the major and minor version from an SPDX version like `SPDX-2.3`.
+fn spdx_version_number(version: &str) -> Option<(u32, u32)> {
+    let (major, minor) = version.strip_prefix("SPDX-")?.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// Get the uppercase SHA256 of the file, or SHA1 if SHA256 doesn't exist.
+fn file_checksum(file: &FileInformation) -> Option<String> {
+    file.checksum(Algorithm::SHA256)
+        .or_else(|| file.checksum(Algorithm::SHA1))
+        .map(str::to_uppercase)
+}
+
+/// Rewrites of the SPDX IDs, external document references and extracted licenses of a document
+/// being merged.
+struct Rewrites {
+    /// New SPDX IDs of the elements of the document.
+    ids: HashMap<String, String>,
+
+    /// New IDs of the renamed external document references.
+    document_refs: HashMap<String, String>,
+
+    /// New IDs of the renamed extracted licenses.
+    license_refs: HashMap<String, String>,
+
+    reference_pattern: Regex,
+}
+
+impl Rewrites {
+    fn new(document_refs: HashMap<String, String>, license_refs: HashMap<String, String>) -> Self {
+        Self {
+            ids: HashMap::new(),
+            document_refs,
+            license_refs,
+            reference_pattern: Regex::new(r"(:?)((?:DocumentRef|LicenseRef)-[A-Za-z0-9.\-]+)")
+                .expect("Valid regex"),
+        }
+    }
+
+    fn insert(&mut self, from: String, to: String) {
+        self.ids.insert(from, to);
+    }
+
+    /// Rewrite the SPDX ID based on the mapping. IDs not in the mapping, like references to
+    /// external documents, are kept as is apart from renamed external document references.
+    fn id(&self, id: &str) -> String {
+        match self.ids.get(id) {
+            Some(id) => id.clone(),
+            None => self.references(id),
+        }
+    }
+
+    /// Rewrite the renamed external document references and extracted licenses in the text.
+    /// Licenses of external documents, like `DocumentRef-x:LicenseRef-y`, are not renamed.
+    fn references(&self, text: &str) -> String {
+        if self.document_refs.is_empty() && self.license_refs.is_empty() {
+            return text.to_string();
+        }
+
+        self.reference_pattern
+            .replace_all(text, |captures: &Captures| {
+                let (separator, reference) = (&captures[1], &captures[2]);
+                let renamed = if reference.starts_with("DocumentRef-") {
+                    self.document_refs.get(reference)
+                } else if separator.is_empty() {
+                    self.license_refs.get(reference)
+                } else {
+                    None
+                };
+
+                format!("{}{}", separator, renamed.map_or(reference, String::as_str))
+            })
+            .into_owned()
+    }
+
+    /// Rewrite the renamed references in the license expression.
+    fn expression(&self, expression: SpdxExpression) -> SpdxExpression {
+        let rewritten = self.references(&expression.to_string());
+        SpdxExpression::parse(&rewritten).unwrap_or(expression)
+    }
+
+    /// Rewrite the renamed references of the license.
+    fn simple_expression(&self, license: SimpleExpression) -> SimpleExpression {
+        let rewritten = self.references(&license.to_string());
+        SimpleExpression::parse(&rewritten).unwrap_or(license)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use spdx_rs::models::{Checksum, ExternalDocumentReference, RelationshipType};
+
+    use super::*;
+
+    fn document(name: &str, package: &str, version: &str, file_sha256: &str) -> SPDX {
+        let mut spdx = SPDX::new(name);
+        spdx.package_information = vec![PackageInformation {
+            package_name: package.to_string(),
+            package_spdx_identifier: "SPDXRef-Package-1".to_string(),
+            package_version: Some(version.to_string()),
+            ..Default::default()
+        }];
+        spdx.file_information = vec![FileInformation {
+            file_name: "main.c".to_string(),
+            file_spdx_identifier: "SPDXRef-File-1".to_string(),
+            file_checksum: vec![Checksum::new(Algorithm::SHA256, file_sha256)],
+            ..Default::default()
+        }];
+        spdx.relationships = vec![
+            Relationship::new(
+                "SPDXRef-DOCUMENT",
+                "SPDXRef-Package-1",
+                RelationshipType::Describes,
+                None,
+            ),
+            Relationship::new(
+                "SPDXRef-Package-1",
+                "SPDXRef-File-1",
+                RelationshipType::Contains,
+                None,
+            ),
+        ];
+
+        spdx
+    }
+
+    #[test]
+    fn duplicates_are_merged_and_ids_rewritten() {
+        let documents = vec![
+            document("image", "busybox", "1.35.0", "aaa"),
+            document("sdk", "busybox", "1.35.0", "aaa"),
+            document("initramfs", "zlib", "1.2.12", "bbb"),
+        ];
+
+        let merged = merge_spdx(documents, "product");
+
+        assert_eq!(merged.package_information.len(), 2);
+        assert_eq!(
+            merged.package_information[0].package_spdx_identifier,
+            "SPDXRef-Package-1"
+        );
+        assert_eq!(
+            merged.package_information[1].package_spdx_identifier,
+            "SPDXRef-Package-1-3"
+        );
+
+        assert_eq!(merged.file_information.len(), 2);
+        assert_eq!(
+            merged.file_information[1].file_spdx_identifier,
+            "SPDXRef-File-1-3"
+        );
+
+        assert_eq!(
+            merged.relationships,
+            vec![
+                Relationship::new(
+                    "SPDXRef-DOCUMENT",
+                    "SPDXRef-Package-1",
+                    RelationshipType::Describes,
+                    None,
+                ),
+                Relationship::new(
+                    "SPDXRef-Package-1",
+                    "SPDXRef-File-1",
+                    RelationshipType::Contains,
+                    None,
+                ),
+                Relationship::new(
+                    "SPDXRef-DOCUMENT",
+                    "SPDXRef-Package-1-3",
+                    RelationshipType::Describes,
+                    None,
+                ),
+                Relationship::new(
+                    "SPDXRef-Package-1-3",
+                    "SPDXRef-File-1-3",
+                    RelationshipType::Contains,
+                    None,
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn clashing_external_document_references_are_renamed() {
+        let mut first = document("image", "busybox", "1.35.0", "aaa");
+        let mut second = document("sdk", "zlib", "1.2.12", "bbb");
+        second.document_creation_information.spdx_version = "SPDX-2.3".to_string();
+
+        for (spdx, uri) in [(&mut first, "https://image"), (&mut second, "https://sdk")] {
+            spdx.document_creation_information
+                .external_document_references
+                .push(ExternalDocumentReference::new(
+                    "DocumentRef-product".to_string(),
+                    uri.to_string(),
+                    Checksum::new(Algorithm::SHA1, "abc"),
+                ));
+            spdx.package_information[0].concluded_license =
+                SpdxExpression::parse("MIT AND DocumentRef-product:LicenseRef-1").unwrap();
+            spdx.relationships.push(Relationship::new(
+                "SPDXRef-Package-1",
+                "DocumentRef-product:SPDXRef-DOCUMENT",
+                RelationshipType::DependsOn,
+                None,
+            ));
+        }
+
+        let merged = merge_spdx(vec![first, second], "product");
+
+        assert_eq!(
+            merged.document_creation_information.spdx_version,
+            "SPDX-2.3"
+        );
+        assert_eq!(
+            merged
+                .document_creation_information
+                .external_document_references
+                .iter()
+                .map(|reference| reference.id_string.as_str())
+                .collect::<Vec<_>>(),
+            vec!["DocumentRef-product", "DocumentRef-product-2"]
+        );
+        assert_eq!(
+            merged.package_information[1].concluded_license,
+            SpdxExpression::parse("MIT AND DocumentRef-product-2:LicenseRef-1").unwrap()
+        );
+        assert!(merged.relationships.contains(&Relationship::new(
+            "SPDXRef-Package-1-2",
+            "DocumentRef-product-2:SPDXRef-DOCUMENT",
+            RelationshipType::DependsOn,
+            None,
+        )));
+    }
+
+    #[test]
+    fn clashing_extracted_licenses_are_renamed() {
+        let documents = ["aaa", "bbb", "ccc"]
+            .iter()
+            .zip(["First text", "Second text", "First text"])
+            .map(|(sha256, text)| {
+                let mut spdx = document(sha256, sha256, "1.0", sha256);
+                spdx.file_information[0].license_information_in_file =
+                    vec![SimpleExpression::parse("LicenseRef-Custom").unwrap()];
+                spdx.package_information[0].concluded_license = SpdxExpression::parse(
+                    "LicenseRef-Custom AND DocumentRef-other:LicenseRef-Custom",
+                )
+                .unwrap();
+                spdx.other_licensing_information_detected =
+                    vec![OtherLicensingInformationDetected {
+                        license_identifier: "LicenseRef-Custom".to_string(),
+                        extracted_text: text.to_string(),
+                        ..Default::default()
+                    }];
+                spdx
+            })
+            .collect();
+
+        let merged = merge_spdx(documents, "product");
+
+        assert_eq!(
+            merged
+                .other_licensing_information_detected
+                .iter()
+                .map(|license| license.license_identifier.as_str())
+                .collect::<Vec<_>>(),
+            vec!["LicenseRef-Custom", "LicenseRef-Custom-2"]
+        );
+        assert_eq!(
+            merged
+                .file_information
+                .iter()
+                .map(|file| file.license_information_in_file[0].to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "LicenseRef-Custom",
+                "LicenseRef-Custom-2",
+                "LicenseRef-Custom"
+            ]
+        );
+        assert_eq!(
+            merged.package_information[1].concluded_license,
+            SpdxExpression::parse("LicenseRef-Custom-2 AND DocumentRef-other:LicenseRef-Custom")
+                .unwrap()
+        );
+    }
+}
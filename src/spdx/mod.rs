@@ -0,0 +1,21 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Functionality for processing SPDX documents.
+
+mod diff;
+mod merge;
+mod split;
+mod stream;
+mod tag_value;
+mod v2_3;
+mod validate;
+
+pub use diff::diff_spdx;
+pub use merge::merge_spdx;
+pub use split::{package_document_file_names, split_spdx};
+pub use stream::{read_spdx_streaming, SpdxStreamWriter};
+pub use tag_value::{algorithm_name, spdx_from_tag_value, spdx_to_tag_value};
+pub use v2_3::{spdx_from_value, spdx_to_value};
+pub use validate::{validate_spdx, Severity};
@@ -0,0 +1,353 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::collections::{HashMap, HashSet};
+
+use log::info;
+use spdx_rs::models::{
+    Checksum, ExternalDocumentReference, FileInformation, PackageInformation, Relationship,
+    RelationshipType, SPDX,
+};
+
+use crate::utilities::files_by_package;
+
+/// Split the [`SPDX`] into one document per package. Each document contains the package, the
+/// files it contains, the relationships of those elements and the extracted licensing infos
+/// they reference. Elements of the product document outside the package are referenced through
+/// an external document reference with the given checksum of the product document.
+pub fn split_spdx(product: &SPDX, product_checksum: &Checksum) -> Vec<SPDX> {
+    info!(
+        "Splitting SPDX into {} documents.",
+        product.package_information.len()
+    );
+
+    let product_information = &product.document_creation_information;
+    let document_ref = format!(
+        "DocumentRef-{}",
+        sanitize_id_string(&product_information.document_name)
+    );
+    let external_reference = ExternalDocumentReference::new(
+        document_ref.clone(),
+        product_information.spdx_document_namespace.clone(),
+        product_checksum.clone(),
+    );
+
+    let files_by_package = files_by_package(product);
+
+    product
+        .package_information
+        .iter()
+        .map(|package| {
+            let files = files_by_package
+                .get(package.package_spdx_identifier.as_str())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+
+            split_package(product, package, files, &document_ref, &external_reference)
+        })
+        .collect()
+}
+
+/// Create the document for a single package.
+fn split_package(
+    product: &SPDX,
+    package: &PackageInformation,
+    files: &[&FileInformation],
+    document_ref: &str,
+    external_reference: &ExternalDocumentReference,
+) -> SPDX {
+    let mut spdx = SPDX::new(&package_document_name(package));
+    spdx.document_creation_information.spdx_version =
+        product.document_creation_information.spdx_version.clone();
+    let document_id = spdx.document_creation_information.spdx_identifier.clone();
+    let product_document_id = &product.document_creation_information.spdx_identifier;
+
+    spdx.document_creation_information.creation_info.creators = vec![format!(
+        "Tool: {}-{}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )];
+    spdx.document_creation_information
+        .external_document_references
+        .push(external_reference.clone());
+
+    spdx.package_information.push(package.clone());
+    spdx.file_information = files.iter().map(|&file| file.clone()).collect();
+
+    let local_ids = spdx
+        .file_information
+        .iter()
+        .map(|file| file.file_spdx_identifier.as_str())
+        .chain([package.package_spdx_identifier.as_str()])
+        .collect::<HashSet<_>>();
+
+    let rewrite_id = |id: &str| {
+        if local_ids.contains(id) || id.starts_with("DocumentRef-") {
+            id.to_string()
+        } else if id == product_document_id {
+            document_id.clone()
+        } else {
+            format!("{}:{}", document_ref, id)
+        }
+    };
+
+    spdx.relationships.push(Relationship::new(
+        &document_id,
+        &package.package_spdx_identifier,
+        RelationshipType::Describes,
+        None,
+    ));
+
+    for relationship in &product.relationships {
+        let is_local = local_ids.contains(relationship.spdx_element_id.as_str())
+            || local_ids.contains(relationship.related_spdx_element.as_str());
+
+        if !is_local {
+            continue;
+        }
+
+        let relationship = Relationship {
+            spdx_element_id: rewrite_id(&relationship.spdx_element_id),
+            related_spdx_element: rewrite_id(&relationship.related_spdx_element),
+            ..relationship.clone()
+        };
+
+        if !spdx.relationships.contains(&relationship) {
+            spdx.relationships.push(relationship);
+        }
+    }
+
+    let mut license_ids = package.concluded_license.identifiers();
+    license_ids.extend(package.declared_license.identifiers());
+    license_ids.extend(package.all_licenses_information_from_files.iter().cloned());
+    for file in &spdx.file_information {
+        license_ids.extend(file.concluded_license.identifiers());
+        license_ids.extend(
+            file.license_information_in_file
+                .iter()
+                .map(ToString::to_string),
+        );
+    }
+
+    spdx.other_licensing_information_detected = product
+        .other_licensing_information_detected
+        .iter()
+        .filter(|license| license_ids.contains(&license.license_identifier))
+        .cloned()
+        .collect();
+
+    spdx
+}
+
+/// Name of the document created for the package.
+fn package_document_name(package: &PackageInformation) -> String {
+    match &package.package_version {
+        Some(version) => format!("{}-{}", package.package_name, version),
+        None => package.package_name.clone(),
+    }
+}
+
+/// File names (without extension) for the documents created by [`split_spdx`]. Packages with the
+/// same name and version get the SPDX identifier of the package appended to the file name, so
+/// their documents don't overwrite each other.
+pub fn package_document_file_names(documents: &[SPDX]) -> Vec<String> {
+    let names = documents
+        .iter()
+        .map(|spdx| package_document_name(&spdx.package_information[0]).replace('/', "_"))
+        .collect::<Vec<_>>();
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for name in &names {
+        *counts.entry(name).or_default() += 1;
+    }
+
+    names
+        .iter()
+        .zip(documents)
+        .map(|(name, spdx)| {
+            if counts[name.as_str()] > 1 {
+                let id = &spdx.package_information[0].package_spdx_identifier;
+                format!(
+                    "{}-{}",
+                    name,
+                    sanitize_id_string(id.trim_start_matches("SPDXRef-"))
+                )
+            } else {
+                name.clone()
+            }
+        })
+        .collect()
+}
+
+/// Replace the characters not allowed in SPDX ID strings with `-`.
+fn sanitize_id_string(input: &str) -> String {
+    input
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() || character == '.' || character == '-' {
+                character
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use spdx_rs::models::{
+        Algorithm, OtherLicensingInformationDetected, SimpleExpression, SpdxExpression,
+    };
+
+    use super::*;
+
+    #[test]
+    fn packages_are_split_to_documents() {
+        let mut product = SPDX::new("Product Image");
+        product.package_information = vec![
+            PackageInformation {
+                package_name: "busybox".to_string(),
+                package_spdx_identifier: "SPDXRef-busybox".to_string(),
+                package_version: Some("1.35.0".to_string()),
+                ..Default::default()
+            },
+            PackageInformation {
+                package_name: "zlib".to_string(),
+                package_spdx_identifier: "SPDXRef-zlib".to_string(),
+                ..Default::default()
+            },
+        ];
+        product.file_information = vec![
+            FileInformation {
+                file_name: "ls.c".to_string(),
+                file_spdx_identifier: "SPDXRef-File-1".to_string(),
+                license_information_in_file: vec![
+                    SimpleExpression::parse("LicenseRef-Custom").unwrap()
+                ],
+                ..Default::default()
+            },
+            FileInformation {
+                file_name: "zlib.c".to_string(),
+                file_spdx_identifier: "SPDXRef-File-2".to_string(),
+                concluded_license: SpdxExpression::parse("LicenseRef-Other").unwrap(),
+                ..Default::default()
+            },
+        ];
+        product.relationships = vec![
+            Relationship::new(
+                "SPDXRef-DOCUMENT",
+                "SPDXRef-busybox",
+                RelationshipType::Describes,
+                None,
+            ),
+            Relationship::new(
+                "SPDXRef-busybox",
+                "SPDXRef-File-1",
+                RelationshipType::Contains,
+                None,
+            ),
+            Relationship::new(
+                "SPDXRef-zlib",
+                "SPDXRef-File-2",
+                RelationshipType::Contains,
+                None,
+            ),
+            Relationship::new(
+                "SPDXRef-busybox",
+                "SPDXRef-zlib",
+                RelationshipType::DependsOn,
+                None,
+            ),
+        ];
+        product.other_licensing_information_detected = vec![
+            OtherLicensingInformationDetected {
+                license_identifier: "LicenseRef-Custom".to_string(),
+                ..Default::default()
+            },
+            OtherLicensingInformationDetected {
+                license_identifier: "LicenseRef-Other".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let checksum = Checksum::new(Algorithm::SHA1, "abc");
+        let documents = split_spdx(&product, &checksum);
+
+        assert_eq!(documents.len(), 2);
+
+        let busybox = &documents[0];
+        assert_eq!(
+            busybox.document_creation_information.document_name,
+            "busybox-1.35.0"
+        );
+        assert_eq!(
+            busybox
+                .document_creation_information
+                .external_document_references[0]
+                .id_string,
+            "DocumentRef-Product-Image"
+        );
+        assert_eq!(busybox.file_information.len(), 1);
+        assert_eq!(
+            busybox.relationships,
+            vec![
+                Relationship::new(
+                    "SPDXRef-DOCUMENT",
+                    "SPDXRef-busybox",
+                    RelationshipType::Describes,
+                    None,
+                ),
+                Relationship::new(
+                    "SPDXRef-busybox",
+                    "SPDXRef-File-1",
+                    RelationshipType::Contains,
+                    None,
+                ),
+                Relationship::new(
+                    "SPDXRef-busybox",
+                    "DocumentRef-Product-Image:SPDXRef-zlib",
+                    RelationshipType::DependsOn,
+                    None,
+                ),
+            ]
+        );
+        assert_eq!(busybox.other_licensing_information_detected.len(), 1);
+        assert_eq!(
+            busybox.other_licensing_information_detected[0].license_identifier,
+            "LicenseRef-Custom"
+        );
+
+        let zlib = &documents[1];
+        assert_eq!(zlib.file_information[0].file_name, "zlib.c");
+        assert_eq!(
+            zlib.other_licensing_information_detected[0].license_identifier,
+            "LicenseRef-Other"
+        );
+    }
+
+    #[test]
+    fn packages_with_the_same_name_get_unique_file_names() {
+        let mut product = SPDX::new("Product");
+        product.package_information = [
+            ("zlib", "SPDXRef-zlib-1"),
+            ("zlib", "SPDXRef-zlib-2"),
+            ("busybox", "SPDXRef-busybox"),
+        ]
+        .iter()
+        .map(|(name, id)| PackageInformation {
+            package_name: name.to_string(),
+            package_spdx_identifier: id.to_string(),
+            package_version: Some("1.0".to_string()),
+            ..Default::default()
+        })
+        .collect();
+
+        let documents = split_spdx(&product, &Checksum::new(Algorithm::SHA1, "abc"));
+
+        assert_eq!(
+            package_document_file_names(&documents),
+            vec!["zlib-1.0-zlib-1", "zlib-1.0-zlib-2", "busybox-1.0"]
+        );
+    }
+}
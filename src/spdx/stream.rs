@@ -0,0 +1,218 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::{
+    fmt,
+    io::{BufReader, Read, Write},
+};
+
+use serde::{
+    de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor},
+    Deserializer,
+};
+use serde_json::{Map, Value};
+use spdx_rs::models::{FileInformation, SPDX};
+
+use super::v2_3::{file_from_value, spdx_from_value, spdx_to_value};
+
+/// Read a JSON SPDX document, passing the entries of `files` to `process_file` one at a time
+/// instead of keeping them in memory. Returns the rest of the document without the files. Only
+/// the files are streamed, so the memory usage still grows with the relationships and the other
+/// fields of the document.
+pub fn read_spdx_streaming<R, F>(reader: R, process_file: F) -> anyhow::Result<SPDX>
+where
+    R: Read,
+    F: FnMut(FileInformation) -> anyhow::Result<()>,
+{
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+    let document = deserializer.deserialize_map(DocumentVisitor { process_file })?;
+    deserializer.end()?;
+
+    spdx_from_value(Value::Object(document))
+}
+
+/// Collects the fields of the document other than `files`.
+struct DocumentVisitor<F> {
+    process_file: F,
+}
+
+impl<'de, F> Visitor<'de> for DocumentVisitor<F>
+where
+    F: FnMut(FileInformation) -> anyhow::Result<()>,
+{
+    type Value = Map<String, Value>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "an SPDX document")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut document = Map::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "files" {
+                map.next_value_seed(FilesVisitor {
+                    process_file: &mut self.process_file,
+                })?;
+            } else {
+                let value = map.next_value()?;
+                document.insert(key, value);
+            }
+        }
+
+        Ok(document)
+    }
+}
+
+/// Deserializes the entries of `files` one at a time.
+struct FilesVisitor<'a, F> {
+    process_file: &'a mut F,
+}
+
+impl<'de, 'a, F> DeserializeSeed<'de> for FilesVisitor<'a, F>
+where
+    F: FnMut(FileInformation) -> anyhow::Result<()>,
+{
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a, F> Visitor<'de> for FilesVisitor<'a, F>
+where
+    F: FnMut(FileInformation) -> anyhow::Result<()>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a list of files")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(file) = seq.next_element::<Value>()? {
+            let file =
+                file_from_value(file).map_err(|err| A::Error::custom(format!("{:#}", err)))?;
+            (self.process_file)(file).map_err(|err| A::Error::custom(format!("{:#}", err)))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a JSON SPDX document one file at a time. The files are written first and the rest of
+/// the document with [`SpdxStreamWriter::finish`].
+pub struct SpdxStreamWriter<W: Write> {
+    writer: W,
+    compact: bool,
+    files_written: usize,
+}
+
+impl<W: Write> SpdxStreamWriter<W> {
+    /// Start writing the document.
+    pub fn new(mut writer: W, compact: bool) -> anyhow::Result<Self> {
+        if compact {
+            write!(writer, "{{\"files\":[")?;
+        } else {
+            write!(writer, "{{\n  \"files\": [")?;
+        }
+
+        Ok(Self {
+            writer,
+            compact,
+            files_written: 0,
+        })
+    }
+
+    /// Write a single file to the document.
+    pub fn write_file(&mut self, file: &FileInformation) -> anyhow::Result<()> {
+        if self.files_written > 0 {
+            write!(self.writer, ",")?;
+        }
+
+        if self.compact {
+            serde_json::to_writer(&mut self.writer, file)?;
+        } else {
+            write!(self.writer, "\n    {}", pretty_json(file, 2)?)?;
+        }
+
+        self.files_written += 1;
+
+        Ok(())
+    }
+
+    /// Write the fields of the document other than `files` and end the document. Returns the
+    /// underlying writer.
+    pub fn finish(mut self, spdx: &SPDX) -> anyhow::Result<W> {
+        let mut document = match spdx_to_value(spdx)? {
+            Value::Object(document) => document,
+            _ => unreachable!("SPDX is always serialized as an object"),
+        };
+        document.remove("files");
+
+        if !self.compact && self.files_written > 0 {
+            write!(self.writer, "\n  ")?;
+        }
+        write!(self.writer, "]")?;
+
+        for (key, value) in document {
+            if self.compact {
+                write!(self.writer, ",{}:", serde_json::to_string(&key)?)?;
+                serde_json::to_writer(&mut self.writer, &value)?;
+            } else {
+                write!(
+                    self.writer,
+                    ",\n  {}: {}",
+                    serde_json::to_string(&key)?,
+                    pretty_json(&value, 1)?
+                )?;
+            }
+        }
+
+        if self.compact {
+            write!(self.writer, "}}")?;
+        } else {
+            write!(self.writer, "\n}}")?;
+        }
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+/// Serialize the value as pretty JSON indented to the given level.
+fn pretty_json<T: serde::Serialize>(value: &T, level: usize) -> anyhow::Result<String> {
+    let indentation = format!("\n{}", "  ".repeat(level));
+
+    Ok(serde_json::to_string_pretty(value)?.replace('\n', &indentation))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    #[test]
+    fn streamed_document_equals_the_original() {
+        let path = "tests/examples/spdx/SPDXJSONExample-v2.2.spdx.json";
+        let original: SPDX = serde_json::from_reader(File::open(path).unwrap()).unwrap();
+
+        for compact in [false, true] {
+            let mut output = Vec::new();
+            let mut writer = SpdxStreamWriter::new(&mut output, compact).unwrap();
+
+            let spdx =
+                read_spdx_streaming(File::open(path).unwrap(), |file| writer.write_file(&file))
+                    .unwrap();
+            assert!(spdx.file_information.is_empty());
+
+            writer.finish(&spdx).unwrap();
+
+            let streamed: SPDX = serde_json::from_slice(&output).unwrap();
+            assert_eq!(streamed, original);
+        }
+    }
+}
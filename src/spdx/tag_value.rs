@@ -0,0 +1,544 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::fmt::Write;
+
+use serde::Serialize;
+use spdx_rs::models::{
+    Algorithm, Annotation, Checksum, FileInformation, OtherLicensingInformationDetected,
+    PackageInformation, Pointer, Relationship, RelationshipType, Snippet, SPDX,
+};
+
+use super::v2_3::{is_spdx_2_3, package_field, relationship_type};
+
+/// Parse [`SPDX`] from a tag-value document.
+pub fn spdx_from_tag_value(input: &str) -> anyhow::Result<SPDX> {
+    let mut spdx = spdx_rs::parsers::spdx_from_tag_value(input)?;
+
+    // The parser drops the prefix of external document references, unlike the JSON format.
+    for reference in &mut spdx
+        .document_creation_information
+        .external_document_references
+    {
+        if !reference.id_string.starts_with("DocumentRef-") {
+            reference.id_string = format!("DocumentRef-{}", reference.id_string);
+        }
+    }
+
+    // Ranges of tag-value snippets always refer to the file the snippet is from.
+    for snippet in &mut spdx.snippet_information {
+        for range in &mut snippet.ranges {
+            for pointer in [&mut range.start_pointer, &mut range.end_pointer] {
+                let (Pointer::Byte { reference, .. } | Pointer::Line { reference, .. }) = pointer;
+                if reference.is_none() {
+                    *reference = Some(snippet.snippet_from_file_spdx_identifier.clone());
+                }
+            }
+        }
+    }
+
+    Ok(spdx)
+}
+
+/// Write the [`SPDX`] as a tag-value document.
+///
+/// Files and snippets are written before the packages, as the tag-value format implies a
+/// `CONTAINS` relationship for files following a package. Packages' `hasFiles` and the document's
+/// `documentDescribes` are written as relationships. The SPDX 2.3 package fields and relationship
+/// types are written only for SPDX 2.3 documents.
+pub fn spdx_to_tag_value(spdx: &SPDX) -> anyhow::Result<String> {
+    let mut output = String::new();
+    let spdx_2_3 = is_spdx_2_3(spdx);
+
+    write_document_creation_information(&mut output, spdx)?;
+
+    for file in &spdx.file_information {
+        write_file(&mut output, file)?;
+    }
+
+    for snippet in &spdx.snippet_information {
+        write_snippet(&mut output, snippet)?;
+    }
+
+    for package in &spdx.package_information {
+        write_package(&mut output, package, spdx_2_3)?;
+    }
+
+    for license in &spdx.other_licensing_information_detected {
+        write_extracted_license(&mut output, license)?;
+    }
+
+    if !spdx.relationships.is_empty() {
+        writeln!(output)?;
+        writeln!(output, "## Relationships")?;
+    }
+    for relationship in relationships(spdx) {
+        write_relationship(&mut output, &relationship, spdx_2_3)?;
+    }
+
+    let annotations = spdx.annotations.iter().chain(
+        spdx.package_information
+            .iter()
+            .flat_map(|package| package.annotations.iter()),
+    );
+    for annotation in annotations {
+        write_annotation(&mut output, annotation)?;
+    }
+
+    Ok(output)
+}
+
+/// Relationships of the document, including the ones implied by `documentDescribes` and
+/// `hasFiles`.
+fn relationships(spdx: &SPDX) -> Vec<Relationship> {
+    let document_id = &spdx.document_creation_information.spdx_identifier;
+    let mut relationships = spdx.relationships.clone();
+
+    let implied = spdx
+        .document_creation_information
+        .document_describes
+        .iter()
+        .map(|described| {
+            Relationship::new(document_id, described, RelationshipType::Describes, None)
+        })
+        .chain(spdx.package_information.iter().flat_map(|package| {
+            package.files.iter().map(move |file| {
+                Relationship::new(
+                    &package.package_spdx_identifier,
+                    file,
+                    RelationshipType::Contains,
+                    None,
+                )
+            })
+        }))
+        .collect::<Vec<_>>();
+
+    for relationship in implied {
+        if !relationships.iter().any(|existing| {
+            existing.spdx_element_id == relationship.spdx_element_id
+                && existing.related_spdx_element == relationship.related_spdx_element
+                && existing.relationship_type == relationship.relationship_type
+        }) {
+            relationships.push(relationship);
+        }
+    }
+
+    relationships
+}
+
+/// Write a single tag-value pair.
+fn write_tag(output: &mut String, tag: &str, value: &str) -> std::fmt::Result {
+    writeln!(output, "{}: {}", tag, value)
+}
+
+/// Write a free-form text value. Multi-line and empty values are wrapped in `<text>`.
+fn write_text(output: &mut String, tag: &str, value: &str) -> std::fmt::Result {
+    if value.is_empty() || value.contains('\n') {
+        writeln!(output, "{}: <text>{}</text>", tag, value)
+    } else {
+        write_tag(output, tag, value)
+    }
+}
+
+fn write_optional_text(output: &mut String, tag: &str, value: &Option<String>) -> std::fmt::Result {
+    match value {
+        Some(value) => write_text(output, tag, value),
+        None => Ok(()),
+    }
+}
+
+fn write_checksum(output: &mut String, tag: &str, checksum: &Checksum) -> std::fmt::Result {
+    writeln!(
+        output,
+        "{}: {}: {}",
+        tag,
+        algorithm_name(checksum.algorithm),
+        checksum.value
+    )
+}
+
+/// Name of the checksum algorithm in the SPDX formats.
+pub fn algorithm_name(algorithm: Algorithm) -> &'static str {
+    match algorithm {
+        Algorithm::SHA1 => "SHA1",
+        Algorithm::SHA224 => "SHA224",
+        Algorithm::SHA256 => "SHA256",
+        Algorithm::SHA384 => "SHA384",
+        Algorithm::SHA512 => "SHA512",
+        Algorithm::MD2 => "MD2",
+        Algorithm::MD4 => "MD4",
+        Algorithm::MD5 => "MD5",
+        Algorithm::MD6 => "MD6",
+    }
+}
+
+/// Get the name of the value in the SPDX formats, e.g. `DESCRIBES` for
+/// [`RelationshipType::Describes`].
+fn serialized_name<T: Serialize>(value: &T) -> anyhow::Result<String> {
+    match serde_json::to_value(value)? {
+        serde_json::Value::String(name) => Ok(name),
+        value => Err(anyhow::anyhow!("{} can't be written as tag-value", value)),
+    }
+}
+
+fn write_document_creation_information(output: &mut String, spdx: &SPDX) -> anyhow::Result<()> {
+    let information = &spdx.document_creation_information;
+
+    write_tag(output, "SPDXVersion", &information.spdx_version)?;
+    write_tag(output, "DataLicense", &information.data_license)?;
+    write_tag(output, "SPDXID", &information.spdx_identifier)?;
+    write_tag(output, "DocumentName", &information.document_name)?;
+    write_tag(
+        output,
+        "DocumentNamespace",
+        &information.spdx_document_namespace,
+    )?;
+    for reference in &information.external_document_references {
+        writeln!(
+            output,
+            "ExternalDocumentRef: {} {} {}: {}",
+            reference.id_string,
+            reference.spdx_document_uri,
+            algorithm_name(reference.checksum.algorithm),
+            reference.checksum.value
+        )?;
+    }
+    write_optional_text(output, "DocumentComment", &information.document_comment)?;
+
+    writeln!(output)?;
+    writeln!(output, "## Creation Information")?;
+    if let Some(version) = &information.creation_info.license_list_version {
+        write_tag(output, "LicenseListVersion", version)?;
+    }
+    for creator in &information.creation_info.creators {
+        write_tag(output, "Creator", creator)?;
+    }
+    write_tag(
+        output,
+        "Created",
+        &serialized_name(&information.creation_info.created)?,
+    )?;
+    write_optional_text(
+        output,
+        "CreatorComment",
+        &information.creation_info.creator_comment,
+    )?;
+
+    Ok(())
+}
+
+fn write_file(output: &mut String, file: &FileInformation) -> anyhow::Result<()> {
+    writeln!(output)?;
+    writeln!(output, "## File Information")?;
+    write_tag(output, "FileName", &file.file_name)?;
+    write_tag(output, "SPDXID", &file.file_spdx_identifier)?;
+    for file_type in &file.file_type {
+        write_tag(output, "FileType", &serialized_name(file_type)?)?;
+    }
+    for checksum in &file.file_checksum {
+        write_checksum(output, "FileChecksum", checksum)?;
+    }
+    write_tag(
+        output,
+        "LicenseConcluded",
+        &file.concluded_license.to_string(),
+    )?;
+    for license in &file.license_information_in_file {
+        write_tag(output, "LicenseInfoInFile", &license.to_string())?;
+    }
+    write_optional_text(output, "LicenseComments", &file.comments_on_license)?;
+    write_text(output, "FileCopyrightText", &file.copyright_text)?;
+    write_optional_text(output, "FileComment", &file.file_comment)?;
+    write_optional_text(output, "FileNotice", &file.file_notice)?;
+    for contributor in &file.file_contributor {
+        write_text(output, "FileContributor", contributor)?;
+    }
+    for text in file.file_attribution_text.iter().flatten() {
+        write_text(output, "FileAttributionText", text)?;
+    }
+
+    Ok(())
+}
+
+fn write_snippet(output: &mut String, snippet: &Snippet) -> anyhow::Result<()> {
+    writeln!(output)?;
+    writeln!(output, "## Snippet Information")?;
+    write_tag(output, "SnippetSPDXID", &snippet.snippet_spdx_identifier)?;
+    write_tag(
+        output,
+        "SnippetFromFileSPDXID",
+        &snippet.snippet_from_file_spdx_identifier,
+    )?;
+    for range in &snippet.ranges {
+        match (&range.start_pointer, &range.end_pointer) {
+            (Pointer::Byte { offset: start, .. }, Pointer::Byte { offset: end, .. }) => {
+                writeln!(output, "SnippetByteRange: {}:{}", start, end)?;
+            }
+            (
+                Pointer::Line {
+                    line_number: start, ..
+                },
+                Pointer::Line {
+                    line_number: end, ..
+                },
+            ) => writeln!(output, "SnippetLineRange: {}:{}", start, end)?,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "range of {} mixes bytes and lines",
+                    snippet.snippet_spdx_identifier
+                ))
+            }
+        }
+    }
+    write_tag(
+        output,
+        "SnippetLicenseConcluded",
+        &snippet.snippet_concluded_license.to_string(),
+    )?;
+    for license in &snippet.license_information_in_snippet {
+        write_tag(output, "LicenseInfoInSnippet", license)?;
+    }
+    write_optional_text(
+        output,
+        "SnippetLicenseComments",
+        &snippet.snippet_comments_on_license,
+    )?;
+    write_text(
+        output,
+        "SnippetCopyrightText",
+        &snippet.snippet_copyright_text,
+    )?;
+    write_optional_text(output, "SnippetComment", &snippet.snippet_comment)?;
+    write_optional_text(output, "SnippetName", &snippet.snippet_name)?;
+    write_optional_text(
+        output,
+        "SnippetAttributionText",
+        &snippet.snippet_attribution_text,
+    )?;
+
+    Ok(())
+}
+
+fn write_package(
+    output: &mut String,
+    package: &PackageInformation,
+    spdx_2_3: bool,
+) -> anyhow::Result<()> {
+    writeln!(output)?;
+    writeln!(output, "## Package Information")?;
+    write_tag(output, "PackageName", &package.package_name)?;
+    write_tag(output, "SPDXID", &package.package_spdx_identifier)?;
+    write_optional_text(output, "PackageVersion", &package.package_version)?;
+    write_optional_text(output, "PackageFileName", &package.package_file_name)?;
+    write_optional_text(output, "PackageSupplier", &package.package_supplier)?;
+    write_optional_text(output, "PackageOriginator", &package.package_originator)?;
+    write_tag(
+        output,
+        "PackageDownloadLocation",
+        &package.package_download_location,
+    )?;
+    if let Some(files_analyzed) = package.files_analyzed {
+        write_tag(output, "FilesAnalyzed", &files_analyzed.to_string())?;
+    }
+    if let Some(verification_code) = &package.package_verification_code {
+        if verification_code.excludes.is_empty() {
+            write_tag(output, "PackageVerificationCode", &verification_code.value)?;
+        } else {
+            writeln!(
+                output,
+                "PackageVerificationCode: {} (excludes: {})",
+                verification_code.value,
+                verification_code.excludes.join(" ")
+            )?;
+        }
+    }
+    for checksum in &package.package_checksum {
+        write_checksum(output, "PackageChecksum", checksum)?;
+    }
+    write_optional_text(output, "PackageHomePage", &package.package_home_page)?;
+    write_optional_text(output, "PackageSourceInfo", &package.source_information)?;
+    write_tag(
+        output,
+        "PackageLicenseConcluded",
+        &package.concluded_license.to_string(),
+    )?;
+    for license in &package.all_licenses_information_from_files {
+        write_tag(output, "PackageLicenseInfoFromFiles", license)?;
+    }
+    write_tag(
+        output,
+        "PackageLicenseDeclared",
+        &package.declared_license.to_string(),
+    )?;
+    write_optional_text(
+        output,
+        "PackageLicenseComments",
+        &package.comments_on_license,
+    )?;
+    write_text(output, "PackageCopyrightText", &package.copyright_text)?;
+    write_optional_text(
+        output,
+        "PackageSummary",
+        &package.package_summary_description,
+    )?;
+    write_optional_text(
+        output,
+        "PackageDescription",
+        &package.package_detailed_description,
+    )?;
+    write_optional_text(output, "PackageComment", &package.package_comment)?;
+    for reference in &package.external_reference {
+        if let Some(field) = package_field(reference) {
+            if spdx_2_3 {
+                write_tag(output, &tag_name(field), &reference.reference_locator)?;
+            }
+            continue;
+        }
+
+        writeln!(
+            output,
+            "ExternalRef: {} {} {}",
+            serialized_name(&reference.reference_category)?,
+            reference.reference_type,
+            reference.reference_locator
+        )?;
+        write_optional_text(output, "ExternalRefComment", &reference.reference_comment)?;
+    }
+    for text in &package.package_attribution_text {
+        write_text(output, "PackageAttributionText", text)?;
+    }
+
+    Ok(())
+}
+
+fn write_extracted_license(
+    output: &mut String,
+    license: &OtherLicensingInformationDetected,
+) -> anyhow::Result<()> {
+    writeln!(output)?;
+    writeln!(output, "## License Information")?;
+    write_tag(output, "LicenseID", &license.license_identifier)?;
+    write_text(output, "ExtractedText", &license.extracted_text)?;
+    write_text(output, "LicenseName", &license.license_name)?;
+    for reference in &license.license_cross_reference {
+        write_tag(output, "LicenseCrossReference", reference)?;
+    }
+    write_optional_text(output, "LicenseComment", &license.license_comment)?;
+
+    Ok(())
+}
+
+fn write_relationship(
+    output: &mut String,
+    relationship: &Relationship,
+    spdx_2_3: bool,
+) -> anyhow::Result<()> {
+    let (relationship_type, comment) = match relationship_type(relationship) {
+        Some((relationship_type, comment)) if spdx_2_3 => (
+            relationship_type.to_string(),
+            comment.map(ToString::to_string),
+        ),
+        _ => (
+            serialized_name(&relationship.relationship_type)?,
+            relationship.comment.clone(),
+        ),
+    };
+
+    writeln!(
+        output,
+        "Relationship: {} {} {}",
+        relationship.spdx_element_id, relationship_type, relationship.related_spdx_element
+    )?;
+    write_optional_text(output, "RelationshipComment", &comment)?;
+
+    Ok(())
+}
+
+/// Tag of the SPDX 2.3 package field, e.g. `PrimaryPackagePurpose` for `primaryPackagePurpose`.
+fn tag_name(field: &str) -> String {
+    let mut chars = field.chars();
+    chars
+        .next()
+        .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
+
+fn write_annotation(output: &mut String, annotation: &Annotation) -> anyhow::Result<()> {
+    writeln!(output)?;
+    writeln!(output, "## Annotation")?;
+    write_tag(output, "Annotator", &annotation.annotator)?;
+    write_tag(
+        output,
+        "AnnotationDate",
+        &serialized_name(&annotation.annotation_date)?,
+    )?;
+    write_tag(
+        output,
+        "AnnotationType",
+        &serialized_name(&annotation.annotation_type)?,
+    )?;
+    write_optional_text(output, "SPDXREF", &annotation.spdx_identifier_reference)?;
+    write_text(output, "AnnotationComment", &annotation.annotation_comment)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utilities::{deserialize_spdx, SpdxFormat};
+
+    use super::*;
+
+    #[test]
+    fn tag_value_round_trip_keeps_the_document() {
+        let spdx = deserialize_spdx(
+            "tests/examples/spdx/SPDXJSONExample-v2.2.spdx.json",
+            Some(SpdxFormat::Json),
+        )
+        .unwrap();
+
+        let tag_value = spdx_to_tag_value(&spdx).unwrap();
+        let parsed = spdx_from_tag_value(&tag_value).unwrap();
+
+        assert_eq!(
+            parsed.document_creation_information.document_name,
+            spdx.document_creation_information.document_name
+        );
+        assert_eq!(
+            parsed
+                .document_creation_information
+                .external_document_references,
+            spdx.document_creation_information
+                .external_document_references
+        );
+        assert_eq!(
+            parsed.package_information.len(),
+            spdx.package_information.len()
+        );
+        assert_eq!(parsed.file_information, spdx.file_information);
+        assert_eq!(parsed.snippet_information, spdx.snippet_information);
+        assert_eq!(
+            parsed.other_licensing_information_detected,
+            spdx.other_licensing_information_detected
+        );
+        for relationship in &spdx.relationships {
+            assert!(parsed.relationships.contains(relationship));
+        }
+    }
+
+    #[test]
+    fn spdx_2_3_fields_are_written_only_to_spdx_2_3_documents() {
+        let mut spdx =
+            deserialize_spdx("tests/data/spdx/spdx-2.3.json", Some(SpdxFormat::Json)).unwrap();
+
+        let output = spdx_to_tag_value(&spdx).unwrap();
+        assert!(output.contains("\nPrimaryPackagePurpose: LIBRARY\n"));
+        assert!(!output.contains("SPDX-2.3:"));
+
+        spdx.document_creation_information.spdx_version = "SPDX-2.2".to_string();
+        let output = spdx_to_tag_value(&spdx).unwrap();
+        assert!(!output.contains("PrimaryPackagePurpose"));
+        assert!(!output.contains("SPDX-2.3:"));
+    }
+}
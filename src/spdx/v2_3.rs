@@ -0,0 +1,409 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Reading and writing SPDX 2.3 documents with the SPDX 2.2 model.
+//!
+//! Fields that became optional in SPDX 2.3 are filled with `NOASSERTION` when reading. The package
+//! fields added in SPDX 2.3 are kept as external references of category `OTHER` with the reference
+//! type prefixed with `SPDX-2.3:`, and converted back to fields when writing an SPDX 2.3 document.
+//! The references are removed when writing documents of other versions. Relationship types added
+//! in SPDX 2.3 are kept as `OTHER` relationships with the type in the comment. Checksum algorithms
+//! added in SPDX 2.3 are not supported by the model, and documents using them are rejected.
+
+use anyhow::anyhow;
+use serde_json::{json, Map, Value};
+use spdx_rs::models::{ExternalPackageReference, FileInformation, Relationship, SPDX};
+
+const SPDX_2_3: &str = "SPDX-2.3";
+
+/// Prefix of the reference type of external references holding SPDX 2.3 package fields, and of
+/// the comment of relationships holding SPDX 2.3 relationship types.
+const REFERENCE_TYPE_PREFIX: &str = "SPDX-2.3:";
+
+/// Package fields added in SPDX 2.3.
+const PACKAGE_FIELDS: [&str; 4] = [
+    "primaryPackagePurpose",
+    "releaseDate",
+    "builtDate",
+    "validUntilDate",
+];
+
+/// Relationship types added in SPDX 2.3.
+const RELATIONSHIP_TYPES: [&str; 2] = ["REQUIREMENT_DESCRIPTION_FOR", "SPECIFICATION_FOR"];
+
+/// Checksum algorithms supported by the model.
+const CHECKSUM_ALGORITHMS: [&str; 9] = [
+    "SHA1", "SHA224", "SHA256", "SHA384", "SHA512", "MD2", "MD4", "MD5", "MD6",
+];
+
+/// External reference categories spelled with underscores in SPDX 2.3 and with hyphens in 2.2.
+const REFERENCE_CATEGORIES: [(&str, &str); 2] = [
+    ("PACKAGE_MANAGER", "PACKAGE-MANAGER"),
+    ("PERSISTENT_ID", "PERSISTENT-ID"),
+];
+
+/// Check if the document is an SPDX 2.3 document.
+pub fn is_spdx_2_3(spdx: &SPDX) -> bool {
+    spdx.document_creation_information.spdx_version == SPDX_2_3
+}
+
+/// Deserialize [`SPDX`] from a JSON value, converting SPDX 2.3 documents to the model.
+pub fn spdx_from_value(mut value: Value) -> anyhow::Result<SPDX> {
+    if value.get("spdxVersion").and_then(Value::as_str) == Some(SPDX_2_3) {
+        for package in objects_mut(&mut value, "packages") {
+            check_checksums(package)?;
+            normalize_package(package);
+        }
+
+        for file in objects_mut(&mut value, "files") {
+            check_checksums(file)?;
+            normalize_file(file);
+        }
+
+        for relationship in objects_mut(&mut value, "relationships") {
+            normalize_relationship(relationship);
+        }
+
+        for snippet in objects_mut(&mut value, "snippets") {
+            insert_default(snippet, "licenseConcluded", json!("NOASSERTION"));
+            insert_default(snippet, "copyrightText", json!("NOASSERTION"));
+        }
+    }
+
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Deserialize [`FileInformation`] from a JSON value, allowing the fields that are optional in
+/// SPDX 2.3 to be missing.
+pub fn file_from_value(mut value: Value) -> anyhow::Result<FileInformation> {
+    if let Some(file) = value.as_object_mut() {
+        check_checksums(file)?;
+        normalize_file(file);
+    }
+
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Serialize [`SPDX`] to a JSON value, restoring the SPDX 2.3 fields if the document is an SPDX
+/// 2.3 document and removing them otherwise.
+pub fn spdx_to_value(spdx: &SPDX) -> anyhow::Result<Value> {
+    let mut value = serde_json::to_value(spdx)?;
+
+    if is_spdx_2_3(spdx) {
+        for package in objects_mut(&mut value, "packages") {
+            restore_package(package);
+        }
+
+        for relationship in objects_mut(&mut value, "relationships") {
+            restore_relationship(relationship);
+        }
+    } else {
+        for package in objects_mut(&mut value, "packages") {
+            remove_package_fields(package);
+        }
+    }
+
+    Ok(value)
+}
+
+/// Get the SPDX 2.3 package field held by the external reference, if any.
+pub fn package_field(reference: &ExternalPackageReference) -> Option<&str> {
+    reference
+        .reference_type
+        .strip_prefix(REFERENCE_TYPE_PREFIX)
+        .filter(|field| PACKAGE_FIELDS.contains(field))
+}
+
+/// Get the SPDX 2.3 type and the original comment of the relationship, if the relationship holds
+/// an SPDX 2.3 relationship type.
+pub fn relationship_type(relationship: &Relationship) -> Option<(&str, Option<&str>)> {
+    relationship_type_from_comment(relationship.comment.as_deref()?)
+}
+
+fn relationship_type_from_comment(comment: &str) -> Option<(&str, Option<&str>)> {
+    let comment = comment.strip_prefix(REFERENCE_TYPE_PREFIX)?;
+    let (relationship_type, comment) = match comment.split_once(' ') {
+        Some((relationship_type, comment)) => (relationship_type, Some(comment)),
+        None => (comment, None),
+    };
+
+    RELATIONSHIP_TYPES
+        .contains(&relationship_type)
+        .then_some((relationship_type, comment))
+}
+
+/// Get the objects of the array field of the value.
+fn objects_mut<'a>(
+    value: &'a mut Value,
+    field: &str,
+) -> impl Iterator<Item = &'a mut Map<String, Value>> {
+    value
+        .get_mut(field)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+fn insert_default(object: &mut Map<String, Value>, field: &str, default: Value) {
+    object.entry(field).or_insert(default);
+}
+
+/// Check that the checksums of the package or file use algorithms supported by the model.
+fn check_checksums(object: &Map<String, Value>) -> anyhow::Result<()> {
+    let checksums = object
+        .get("checksums")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten();
+
+    for checksum in checksums {
+        if let Some(algorithm) = checksum.get("algorithm").and_then(Value::as_str) {
+            if !CHECKSUM_ALGORITHMS.contains(&algorithm) {
+                return Err(anyhow!(
+                    "Checksum algorithm {} of {} is not supported",
+                    algorithm,
+                    object
+                        .get("SPDXID")
+                        .and_then(Value::as_str)
+                        .unwrap_or("an element")
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn normalize_file(file: &mut Map<String, Value>) {
+    insert_default(file, "licenseConcluded", json!("NOASSERTION"));
+    insert_default(file, "licenseInfoInFiles", json!([]));
+    insert_default(file, "copyrightText", json!("NOASSERTION"));
+}
+
+fn normalize_package(package: &mut Map<String, Value>) {
+    insert_default(package, "licenseConcluded", json!("NOASSERTION"));
+    insert_default(package, "licenseDeclared", json!("NOASSERTION"));
+    insert_default(package, "copyrightText", json!("NOASSERTION"));
+
+    let mut references = package
+        .remove("externalRefs")
+        .and_then(|references| match references {
+            Value::Array(references) => Some(references),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    for reference in &mut references {
+        if let Some(category) = reference.get_mut("referenceCategory") {
+            if let Some((_, hyphenated)) = REFERENCE_CATEGORIES
+                .iter()
+                .find(|(underscored, _)| category == underscored)
+            {
+                *category = json!(hyphenated);
+            }
+        }
+    }
+
+    for field in PACKAGE_FIELDS {
+        if let Some(value) = package.remove(field) {
+            references.push(json!({
+                "referenceCategory": "OTHER",
+                "referenceType": format!("{}{}", REFERENCE_TYPE_PREFIX, field),
+                "referenceLocator": value,
+            }));
+        }
+    }
+
+    if !references.is_empty() {
+        package.insert("externalRefs".to_string(), Value::Array(references));
+    }
+}
+
+fn normalize_relationship(relationship: &mut Map<String, Value>) {
+    let relationship_type = match relationship.get("relationshipType").and_then(Value::as_str) {
+        Some(relationship_type) if RELATIONSHIP_TYPES.contains(&relationship_type) => {
+            relationship_type.to_string()
+        }
+        _ => return,
+    };
+
+    let comment = match relationship.get("comment").and_then(Value::as_str) {
+        Some(comment) => format!("{}{} {}", REFERENCE_TYPE_PREFIX, relationship_type, comment),
+        None => format!("{}{}", REFERENCE_TYPE_PREFIX, relationship_type),
+    };
+
+    relationship.insert("relationshipType".to_string(), json!("OTHER"));
+    relationship.insert("comment".to_string(), json!(comment));
+}
+
+fn restore_relationship(relationship: &mut Map<String, Value>) {
+    if relationship.get("relationshipType").and_then(Value::as_str) != Some("OTHER") {
+        return;
+    }
+
+    let (relationship_type, comment) = match relationship
+        .get("comment")
+        .and_then(Value::as_str)
+        .and_then(relationship_type_from_comment)
+    {
+        Some((relationship_type, comment)) => (
+            relationship_type.to_string(),
+            comment.map(ToString::to_string),
+        ),
+        None => return,
+    };
+
+    relationship.insert("relationshipType".to_string(), json!(relationship_type));
+    match comment {
+        Some(comment) => relationship.insert("comment".to_string(), json!(comment)),
+        None => relationship.remove("comment"),
+    };
+}
+
+/// Remove the external references holding SPDX 2.3 package fields.
+fn remove_package_fields(package: &mut Map<String, Value>) {
+    let references = match package
+        .get_mut("externalRefs")
+        .and_then(Value::as_array_mut)
+    {
+        Some(references) => references,
+        None => return,
+    };
+
+    references.retain(|reference| {
+        !reference
+            .get("referenceType")
+            .and_then(Value::as_str)
+            .is_some_and(|reference_type| reference_type.starts_with(REFERENCE_TYPE_PREFIX))
+    });
+
+    if references.is_empty() {
+        package.remove("externalRefs");
+    }
+}
+
+fn restore_package(package: &mut Map<String, Value>) {
+    let references = match package.remove("externalRefs") {
+        Some(Value::Array(references)) => references,
+        _ => return,
+    };
+
+    let mut kept = Vec::new();
+
+    for mut reference in references {
+        let field = reference
+            .get("referenceType")
+            .and_then(Value::as_str)
+            .and_then(|reference_type| reference_type.strip_prefix(REFERENCE_TYPE_PREFIX))
+            .filter(|field| PACKAGE_FIELDS.contains(field))
+            .map(ToString::to_string);
+
+        match field {
+            Some(field) => {
+                let value = reference
+                    .get_mut("referenceLocator")
+                    .map(Value::take)
+                    .unwrap_or_default();
+                package.insert(field, value);
+            }
+            None => {
+                if let Some(category) = reference.get_mut("referenceCategory") {
+                    if let Some((underscored, _)) = REFERENCE_CATEGORIES
+                        .iter()
+                        .find(|(_, hyphenated)| category == hyphenated)
+                    {
+                        *category = json!(underscored);
+                    }
+                }
+                kept.push(reference);
+            }
+        }
+    }
+
+    if !kept.is_empty() {
+        package.insert("externalRefs".to_string(), Value::Array(kept));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use super::*;
+
+    #[test]
+    fn spdx_2_3_fields_are_kept() {
+        let input: Value =
+            serde_json::from_str(&read_to_string("tests/data/spdx/spdx-2.3.json").unwrap())
+                .unwrap();
+
+        let spdx = spdx_from_value(input.clone()).unwrap();
+        let package = &spdx.package_information[0];
+        assert_eq!(package.concluded_license.to_string(), "NOASSERTION");
+        assert_eq!(spdx.file_information[0].copyright_text, "NOASSERTION");
+
+        let output = spdx_to_value(&spdx).unwrap();
+        let input_package = &input["packages"][0];
+        let output_package = &output["packages"][0];
+
+        for field in PACKAGE_FIELDS {
+            assert_eq!(output_package[field], input_package[field]);
+        }
+        assert_eq!(
+            output_package["externalRefs"],
+            input_package["externalRefs"]
+        );
+    }
+
+    #[test]
+    fn spdx_2_3_relationship_types_are_kept() {
+        let mut input: Value =
+            serde_json::from_str(&read_to_string("tests/data/spdx/spdx-2.3.json").unwrap())
+                .unwrap();
+        input["relationships"] = json!([
+            {
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relatedSpdxElement": "SPDXRef-Package-zlib",
+                "relationshipType": "SPECIFICATION_FOR",
+                "comment": "Design document"
+            },
+            {
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relatedSpdxElement": "SPDXRef-Package-zlib",
+                "relationshipType": "DESCRIBES"
+            }
+        ]);
+
+        let mut spdx = spdx_from_value(input.clone()).unwrap();
+        let relationship = &spdx.relationships[0];
+        assert_eq!(
+            relationship_type(relationship),
+            Some(("SPECIFICATION_FOR", Some("Design document")))
+        );
+
+        let output = spdx_to_value(&spdx).unwrap();
+        assert_eq!(output["relationships"], input["relationships"]);
+
+        spdx.document_creation_information.spdx_version = "SPDX-2.2".to_string();
+        let output = spdx_to_value(&spdx).unwrap();
+        assert_eq!(output["relationships"][0]["relationshipType"], "OTHER");
+        assert!(output["packages"][0]["externalRefs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|reference| reference["referenceCategory"] != "OTHER"));
+    }
+
+    #[test]
+    fn unsupported_checksum_algorithms_are_rejected() {
+        let mut input: Value =
+            serde_json::from_str(&read_to_string("tests/data/spdx/spdx-2.3.json").unwrap())
+                .unwrap();
+        input["files"][0]["checksums"] = json!([{ "algorithm": "BLAKE3", "checksumValue": "abc" }]);
+
+        let error = spdx_from_value(input).unwrap_err();
+        assert!(error.to_string().starts_with("Checksum algorithm BLAKE3"));
+    }
+}
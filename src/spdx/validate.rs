@@ -0,0 +1,504 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+use std::{collections::HashSet, fmt::Display};
+
+use log::info;
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use spdx_rs::models::{Algorithm, Checksum, PackageInformation, SpdxExpression, SPDX};
+use spdx_toolkit::license_list::LicenseList;
+
+use crate::{spdx::algorithm_name, utilities::files_by_package};
+
+/// Severity of a [`Diagnostic`].
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Problem found when validating an SPDX document.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub severity: Severity,
+
+    /// Machine-readable identifier of the check that failed.
+    pub code: &'static str,
+
+    /// SPDX ID of the element with the problem.
+    pub spdx_id: String,
+
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(code: &'static str, spdx_id: &str, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            spdx_id: spdx_id.to_string(),
+            message,
+        }
+    }
+
+    fn warning(code: &'static str, spdx_id: &str, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            code,
+            spdx_id: spdx_id.to_string(),
+            message,
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+
+        write!(
+            f,
+            "{}[{}] {}: {}",
+            severity, self.code, self.spdx_id, self.message
+        )
+    }
+}
+
+/// Validate the structural integrity of the [`SPDX`] and the license expressions against the
+/// license list.
+pub fn validate_spdx(spdx: &SPDX, license_list: &LicenseList) -> Vec<Diagnostic> {
+    info!("Validating SPDX.");
+
+    let mut diagnostics = Vec::new();
+
+    validate_unique_ids(spdx, &mut diagnostics);
+    validate_relationships(spdx, &mut diagnostics);
+    validate_licenses(spdx, license_list, &mut diagnostics);
+    validate_checksums(spdx, &mut diagnostics);
+    validate_verification_codes(spdx, &mut diagnostics);
+
+    info!(
+        "Validation found {} errors and {} warnings.",
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .count(),
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Warning)
+            .count()
+    );
+
+    diagnostics
+}
+
+/// All SPDX IDs defined in the document.
+fn element_ids(spdx: &SPDX) -> Vec<&str> {
+    let mut ids = vec![spdx.document_creation_information.spdx_identifier.as_str()];
+    ids.extend(
+        spdx.package_information
+            .iter()
+            .map(|package| package.package_spdx_identifier.as_str()),
+    );
+    ids.extend(
+        spdx.file_information
+            .iter()
+            .map(|file| file.file_spdx_identifier.as_str()),
+    );
+    ids.extend(
+        spdx.snippet_information
+            .iter()
+            .map(|snippet| snippet.snippet_spdx_identifier.as_str()),
+    );
+
+    ids
+}
+
+fn validate_unique_ids(spdx: &SPDX, diagnostics: &mut Vec<Diagnostic>) {
+    let mut seen = HashSet::new();
+    let mut reported = HashSet::new();
+
+    for id in element_ids(spdx) {
+        if !seen.insert(id) && reported.insert(id) {
+            diagnostics.push(Diagnostic::error(
+                "duplicate-spdx-id",
+                id,
+                "SPDX ID is used by multiple elements.".to_string(),
+            ));
+        }
+    }
+}
+
+fn validate_relationships(spdx: &SPDX, diagnostics: &mut Vec<Diagnostic>) {
+    let ids = element_ids(spdx).into_iter().collect::<HashSet<_>>();
+    let document_refs = spdx
+        .document_creation_information
+        .external_document_references
+        .iter()
+        .map(|reference| reference.id_string.as_str())
+        .collect::<HashSet<_>>();
+
+    let exists = |id: &str| {
+        if let Some((document_ref, _)) = id.split_once(':') {
+            document_refs.contains(document_ref)
+        } else {
+            ids.contains(id)
+        }
+    };
+
+    for relationship in &spdx.relationships {
+        if !exists(&relationship.spdx_element_id) {
+            diagnostics.push(Diagnostic::error(
+                "dangling-relationship",
+                &relationship.spdx_element_id,
+                format!(
+                    "Element of {} relationship to {} does not exist.",
+                    relationship.relationship_type.as_ref(),
+                    relationship.related_spdx_element
+                ),
+            ));
+        }
+
+        let related = relationship.related_spdx_element.as_str();
+        if related != "NONE" && related != "NOASSERTION" && !exists(related) {
+            diagnostics.push(Diagnostic::error(
+                "dangling-relationship",
+                related,
+                format!(
+                    "Related element of {} relationship from {} does not exist.",
+                    relationship.relationship_type.as_ref(),
+                    relationship.spdx_element_id
+                ),
+            ));
+        }
+    }
+
+    for described in &spdx.document_creation_information.document_describes {
+        if !exists(described) {
+            diagnostics.push(Diagnostic::error(
+                "dangling-relationship",
+                described,
+                "Described element does not exist.".to_string(),
+            ));
+        }
+    }
+}
+
+fn validate_licenses(spdx: &SPDX, license_list: &LicenseList, diagnostics: &mut Vec<Diagnostic>) {
+    let defined_license_refs = spdx
+        .other_licensing_information_detected
+        .iter()
+        .map(|license| license.license_identifier.as_str())
+        .collect::<HashSet<_>>();
+
+    let check_identifiers =
+        |spdx_id: &str, identifiers: HashSet<String>, diagnostics: &mut Vec<Diagnostic>| {
+            let mut identifiers = identifiers.into_iter().collect::<Vec<_>>();
+            identifiers.sort();
+
+            for identifier in identifiers {
+                if identifier.starts_with("LicenseRef-")
+                    && !defined_license_refs.contains(identifier.as_str())
+                {
+                    diagnostics.push(Diagnostic::error(
+                        "undefined-license-ref",
+                        spdx_id,
+                        format!(
+                            "{} is not defined in hasExtractedLicensingInfos.",
+                            identifier
+                        ),
+                    ));
+                } else if !license_list.is_valid_license(&identifier) {
+                    diagnostics.push(Diagnostic::error(
+                        "invalid-license",
+                        spdx_id,
+                        format!(
+                            "{} is not on the SPDX license list {}.",
+                            identifier, license_list.license_list_version
+                        ),
+                    ));
+                }
+            }
+        };
+
+    for package in &spdx.package_information {
+        let id = &package.package_spdx_identifier;
+        let mut identifiers = package.declared_license.identifiers();
+        identifiers.extend(package.concluded_license.identifiers());
+
+        for license in &package.all_licenses_information_from_files {
+            match SpdxExpression::parse(license) {
+                Ok(expression) => identifiers.extend(expression.identifiers()),
+                Err(_) => diagnostics.push(Diagnostic::error(
+                    "invalid-expression",
+                    id,
+                    format!("{} is not a valid license expression.", license),
+                )),
+            }
+        }
+
+        check_identifiers(id, identifiers, diagnostics);
+    }
+
+    for file in &spdx.file_information {
+        let mut identifiers = file.concluded_license.identifiers();
+        identifiers.extend(
+            file.license_information_in_file
+                .iter()
+                .map(ToString::to_string),
+        );
+
+        check_identifiers(&file.file_spdx_identifier, identifiers, diagnostics);
+    }
+
+    for snippet in &spdx.snippet_information {
+        check_identifiers(
+            &snippet.snippet_spdx_identifier,
+            snippet.snippet_concluded_license.identifiers(),
+            diagnostics,
+        );
+    }
+}
+
+/// Expected length of the hex value of the algorithm. `None` for variable length algorithms.
+fn checksum_length(algorithm: Algorithm) -> Option<usize> {
+    match algorithm {
+        Algorithm::SHA1 => Some(40),
+        Algorithm::SHA224 => Some(56),
+        Algorithm::SHA256 => Some(64),
+        Algorithm::SHA384 => Some(96),
+        Algorithm::SHA512 => Some(128),
+        Algorithm::MD2 | Algorithm::MD4 | Algorithm::MD5 => Some(32),
+        Algorithm::MD6 => None,
+    }
+}
+
+fn is_valid_checksum(checksum: &Checksum) -> bool {
+    let is_hex = checksum
+        .value
+        .chars()
+        .all(|character| character.is_ascii_hexdigit());
+
+    is_hex
+        && checksum_length(checksum.algorithm).map_or(!checksum.value.is_empty(), |length| {
+            checksum.value.len() == length
+        })
+}
+
+fn validate_checksums(spdx: &SPDX, diagnostics: &mut Vec<Diagnostic>) {
+    let checksums = spdx
+        .package_information
+        .iter()
+        .flat_map(|package| {
+            package
+                .package_checksum
+                .iter()
+                .map(move |checksum| (package.package_spdx_identifier.as_str(), checksum))
+        })
+        .chain(spdx.file_information.iter().flat_map(|file| {
+            file.file_checksum
+                .iter()
+                .map(move |checksum| (file.file_spdx_identifier.as_str(), checksum))
+        }));
+
+    for (id, checksum) in checksums {
+        if !is_valid_checksum(checksum) {
+            diagnostics.push(Diagnostic::error(
+                "invalid-checksum",
+                id,
+                format!(
+                    "{} checksum {} is not valid.",
+                    algorithm_name(checksum.algorithm),
+                    checksum.value
+                ),
+            ));
+        }
+    }
+}
+
+fn validate_verification_codes(spdx: &SPDX, diagnostics: &mut Vec<Diagnostic>) {
+    let files_by_package = files_by_package(spdx);
+
+    for package in &spdx.package_information {
+        let verification_code = match &package.package_verification_code {
+            Some(verification_code) => verification_code,
+            None => continue,
+        };
+        let id = &package.package_spdx_identifier;
+
+        if !is_valid_checksum(&Checksum::new(Algorithm::SHA1, &verification_code.value)) {
+            diagnostics.push(Diagnostic::error(
+                "invalid-verification-code",
+                id,
+                format!(
+                    "Verification code {} is not a valid SHA1.",
+                    verification_code.value
+                ),
+            ));
+            continue;
+        }
+
+        let files = files_by_package
+            .get(id.as_str())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        if let Some(calculated) = calculate_verification_code(package, files) {
+            if !calculated.eq_ignore_ascii_case(&verification_code.value) {
+                diagnostics.push(Diagnostic::warning(
+                    "verification-code-mismatch",
+                    id,
+                    format!(
+                        "Verification code {} does not match {} calculated from the files.",
+                        verification_code.value, calculated
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// Calculate the verification code of the package from the SHA1s of its files. Returns `None`
+/// if the package has no files or some files don't have a SHA1.
+fn calculate_verification_code(
+    package: &PackageInformation,
+    files: &[&spdx_rs::models::FileInformation],
+) -> Option<String> {
+    let excludes = package
+        .package_verification_code
+        .as_ref()
+        .map(|code| {
+            code.excludes
+                .iter()
+                .map(String::as_str)
+                .collect::<HashSet<_>>()
+        })
+        .unwrap_or_default();
+
+    let mut sha1s = files
+        .iter()
+        .filter(|file| !excludes.contains(file.file_name.as_str()))
+        .map(|file| file.checksum(Algorithm::SHA1).map(str::to_lowercase))
+        .collect::<Option<Vec<_>>>()?;
+
+    if sha1s.is_empty() {
+        return None;
+    }
+
+    sha1s.sort();
+
+    let mut sha1 = Sha1::new();
+    sha1.update(sha1s.concat());
+
+    Some(hex::encode(sha1.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use spdx_rs::models::{
+        FileInformation, PackageVerificationCode, Relationship, RelationshipType, SimpleExpression,
+    };
+
+    use crate::utilities::test_license_list;
+
+    use super::*;
+
+    #[test]
+    fn structural_problems_are_found() {
+        let license_list = test_license_list();
+        let mut spdx = SPDX::new("test_spdx");
+
+        spdx.package_information = vec![PackageInformation {
+            package_name: "busybox".to_string(),
+            package_spdx_identifier: "SPDXRef-busybox".to_string(),
+            declared_license: SpdxExpression::parse("GPL-2.0-only AND LicenseRef-Missing").unwrap(),
+            package_verification_code: Some(PackageVerificationCode::new(
+                "0000000000000000000000000000000000000000".to_string(),
+                Vec::new(),
+            )),
+            ..Default::default()
+        }];
+        spdx.file_information = vec![
+            FileInformation {
+                file_name: "ls.c".to_string(),
+                file_spdx_identifier: "SPDXRef-File-1".to_string(),
+                file_checksum: vec![Checksum::new(
+                    Algorithm::SHA1,
+                    "2fd4e1c67a2d28fced849ee1bb76e7391b93eb12",
+                )],
+                license_information_in_file: vec![SimpleExpression::parse("NotALicense").unwrap()],
+                ..Default::default()
+            },
+            FileInformation {
+                file_name: "cat.c".to_string(),
+                file_spdx_identifier: "SPDXRef-File-1".to_string(),
+                file_checksum: vec![
+                    Checksum::new(Algorithm::SHA1, "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+                    Checksum::new(Algorithm::SHA256, "xyz"),
+                ],
+                ..Default::default()
+            },
+        ];
+        spdx.relationships = vec![
+            Relationship::new(
+                "SPDXRef-busybox",
+                "SPDXRef-File-1",
+                RelationshipType::Contains,
+                None,
+            ),
+            Relationship::new(
+                "SPDXRef-busybox",
+                "SPDXRef-Missing",
+                RelationshipType::DependsOn,
+                None,
+            ),
+        ];
+
+        let codes = validate_spdx(&spdx, &license_list)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.spdx_id))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            codes,
+            vec![
+                ("duplicate-spdx-id", "SPDXRef-File-1".to_string()),
+                ("dangling-relationship", "SPDXRef-Missing".to_string()),
+                ("undefined-license-ref", "SPDXRef-busybox".to_string()),
+                ("invalid-license", "SPDXRef-File-1".to_string()),
+                ("invalid-checksum", "SPDXRef-File-1".to_string()),
+                ("verification-code-mismatch", "SPDXRef-busybox".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn valid_document_has_no_diagnostics() {
+        let license_list = test_license_list();
+        let spdx =
+            crate::utilities::deserialize_spdx("tests/data/spdx/spdx-2.3.json", None).unwrap();
+
+        assert_eq!(validate_spdx(&spdx, &license_list), Vec::new());
+    }
+
+    #[test]
+    fn license_refs_of_external_documents_are_valid() {
+        let license_list = test_license_list();
+        let mut spdx = SPDX::new("test_spdx");
+
+        spdx.package_information = vec![PackageInformation {
+            package_name: "busybox".to_string(),
+            package_spdx_identifier: "SPDXRef-busybox".to_string(),
+            declared_license: SpdxExpression::parse("MIT AND DocumentRef-x:LicenseRef-y").unwrap(),
+            ..Default::default()
+        }];
+
+        assert_eq!(validate_spdx(&spdx, &license_list), Vec::new());
+    }
+}
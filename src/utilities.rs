@@ -2,52 +2,444 @@
 //
 // SPDX-License-Identifier: MIT
 
-use sha2::{Digest, Sha256};
-use spdx_rs::models::SPDX;
+use anyhow::Context;
+use clap::{Args, ValueEnum};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use spdx_rs::models::{Algorithm, Checksum, FileInformation, RelationshipType, SPDX};
 use std::{
-    fs::{read_to_string, write, File},
-    io,
-    path::Path,
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
 };
 
-pub fn hash256_for_path<P: AsRef<Path>>(path: P) -> anyhow::Result<String> {
-    let mut file = File::open(path)?;
-    let mut sha256 = Sha256::new();
-    io::copy(&mut file, &mut sha256)?;
-    let hash = sha256.finalize();
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 
-    Ok(hex::encode_upper(hash))
+use crate::spdx::{spdx_from_tag_value, spdx_from_value, spdx_to_tag_value, spdx_to_value};
+
+/// SHA1, SHA256, SHA512 and MD5 of a file.
+///
+/// The hashes are always lowercase hex like in SPDX documents and the responses of Fossology.
+/// Hashes from other sources should be compared case-insensitively, and converted to uppercase
+/// only when required, e.g. in the filesearch queries to Fossology.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHashes {
+    pub sha1: String,
+    pub sha256: String,
+    pub sha512: String,
+    pub md5: String,
 }
 
-/// Deserialize [`SPDX`] from a file path. Accepts JSON and YAML.
-pub fn deserialize_spdx<P: AsRef<Path>>(path_to_spdx: P) -> anyhow::Result<SPDX> {
-    let file_contents = read_to_string(&path_to_spdx)?;
-    match path_to_spdx.as_ref().extension() {
-        Some(extension) => match extension.to_str() {
-            Some("json") => Ok(serde_json::from_str::<SPDX>(&file_contents)?),
-            Some("yml") | Some("yaml") => Ok(serde_yaml::from_str::<SPDX>(&file_contents)?),
+impl FileHashes {
+    /// Calculate the hashes of the contents of the reader, reading it only once.
+    pub fn from_reader<R: Read>(mut reader: R) -> anyhow::Result<Self> {
+        let mut sha1 = Sha1::new();
+        let mut sha256 = Sha256::new();
+        let mut sha512 = Sha512::new();
+        let mut md5 = Md5::new();
+
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let read = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+            sha1.update(&buffer[..read]);
+            sha256.update(&buffer[..read]);
+            sha512.update(&buffer[..read]);
+            md5.update(&buffer[..read]);
+        }
+
+        Ok(Self {
+            sha1: hex::encode(sha1.finalize()),
+            sha256: hex::encode(sha256.finalize()),
+            sha512: hex::encode(sha512.finalize()),
+            md5: hex::encode(md5.finalize()),
+        })
+    }
+
+    /// The hashes as SPDX checksums.
+    pub fn checksums(&self) -> Vec<Checksum> {
+        vec![
+            Checksum::new(Algorithm::SHA1, &self.sha1),
+            Checksum::new(Algorithm::SHA256, &self.sha256),
+            Checksum::new(Algorithm::SHA512, &self.sha512),
+            Checksum::new(Algorithm::MD5, &self.md5),
+        ]
+    }
+}
+
+/// Calculate the SHA1, SHA256, SHA512 and MD5 of the file at path, reading the file only once.
+pub fn hashes_for_path<P: AsRef<Path>>(path: P) -> anyhow::Result<FileHashes> {
+    FileHashes::from_reader(File::open(path)?)
+}
+
+/// Serialization format of SPDX documents.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpdxFormat {
+    Json,
+    Yaml,
+    TagValue,
+}
+
+impl SpdxFormat {
+    /// Detect the format from the extension of the path, ignoring the extension of the
+    /// compression.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = if is_compressed(&path) {
+            path.as_ref().with_extension("")
+        } else {
+            PathBuf::from(path.as_ref())
+        };
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Ok(Self::Json),
+            Some("yml") | Some("yaml") => Ok(Self::Yaml),
+            Some("spdx") => Ok(Self::TagValue),
             _ => Err(anyhow::anyhow!("invalid file extension")),
-        },
-        None => Err(anyhow::anyhow!("invalid file extension")),
+        }
     }
+
+    /// Extension of SPDX documents in the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "spdx.json",
+            Self::Yaml => "spdx.yaml",
+            Self::TagValue => "spdx",
+        }
+    }
+}
+
+/// Options for reading and writing SPDX documents.
+#[derive(Args, Debug, Clone, Copy, Default)]
+pub struct SpdxOptions {
+    /// Format of the SPDX documents to read and write. Detected from the file extension if not
+    /// given.
+    #[clap(name = "spdx-format", long = "spdx-format", value_enum, global = true)]
+    pub format: Option<SpdxFormat>,
+
+    /// Write JSON SPDX documents without whitespace.
+    #[clap(long, global = true)]
+    pub compact: bool,
+}
+
+/// Deserialize [`SPDX`] from a file path, or from stdin if the path is `-`. Accepts JSON, YAML
+/// and tag-value, optionally compressed with gzip or zstd. The format is detected from the
+/// extension if not given, stdin defaults to JSON.
+pub fn deserialize_spdx<P: AsRef<Path>>(
+    path_to_spdx: P,
+    format: Option<SpdxFormat>,
+) -> anyhow::Result<SPDX> {
+    let format = input_format(&path_to_spdx, format)?;
+
+    let mut file_contents = String::new();
+    open_spdx_reader(&path_to_spdx)?.read_to_string(&mut file_contents)?;
+
+    spdx_from_str(&file_contents, format)
+}
+
+/// Deserialize [`SPDX`] like [`deserialize_spdx`] and calculate the SHA1 of the document as
+/// lowercase hex. The SHA1 is of the decompressed document that was read.
+pub fn deserialize_spdx_with_sha1<P: AsRef<Path>>(
+    path_to_spdx: P,
+    format: Option<SpdxFormat>,
+) -> anyhow::Result<(SPDX, String)> {
+    let format = input_format(&path_to_spdx, format)?;
+
+    let mut file_contents = String::new();
+    open_spdx_reader(&path_to_spdx)?.read_to_string(&mut file_contents)?;
+    let sha1 = hex::encode(Sha1::digest(file_contents.as_bytes()));
+
+    Ok((spdx_from_str(&file_contents, format)?, sha1))
 }
 
-/// Serialize [`SPDX`] to a file path.
-pub fn serialize_spdx<P: AsRef<Path>>(output_path: P, spdx: &SPDX) -> anyhow::Result<()> {
-    let json_string = serde_json::to_string_pretty(&spdx)?;
-    write(&output_path, json_string)?;
+/// Get the format of the input, detected from the extension if not given. Stdin defaults to JSON.
+fn input_format<P: AsRef<Path>>(
+    path_to_spdx: P,
+    format: Option<SpdxFormat>,
+) -> anyhow::Result<SpdxFormat> {
+    match format {
+        Some(format) => Ok(format),
+        None if is_stdio(&path_to_spdx) => Ok(SpdxFormat::Json),
+        None => SpdxFormat::from_path(&path_to_spdx),
+    }
+}
+
+/// Deserialize [`SPDX`] in the format from the contents of a document.
+fn spdx_from_str(file_contents: &str, format: SpdxFormat) -> anyhow::Result<SPDX> {
+    match format {
+        // SPDX 2.3 documents fail to deserialize directly to the model, so they are converted
+        // through a JSON value.
+        SpdxFormat::Json => with_fallback(serde_json::from_str::<SPDX>(file_contents), || {
+            spdx_from_value(serde_json::from_str(file_contents)?)
+        }),
+        SpdxFormat::Yaml => with_fallback(serde_yaml::from_str::<SPDX>(file_contents), || {
+            spdx_from_value(serde_yaml::from_str(file_contents)?)
+        }),
+        SpdxFormat::TagValue => spdx_from_tag_value(file_contents),
+    }
+}
+
+/// Use the fallback if deserializing failed. The original error is kept in the error of the
+/// fallback, as it usually describes the actual problem of a malformed document.
+fn with_fallback<E, F>(result: Result<SPDX, E>, fallback: F) -> anyhow::Result<SPDX>
+where
+    E: std::error::Error,
+    F: FnOnce() -> anyhow::Result<SPDX>,
+{
+    result.or_else(|error| {
+        fallback().with_context(|| format!("Deserializing the SPDX document failed: {}", error))
+    })
+}
+
+/// Serialize [`SPDX`] to a file path, or to stdout if the path is `-`. The format is detected
+/// from the extension if not given, defaulting to JSON. Compressed with gzip or zstd if the path
+/// ends with `.gz` or `.zst`.
+pub fn serialize_spdx<P: AsRef<Path>>(
+    output_path: P,
+    spdx: &SPDX,
+    options: SpdxOptions,
+) -> anyhow::Result<()> {
+    let format = options
+        .format
+        .or_else(|| SpdxFormat::from_path(&output_path).ok())
+        .unwrap_or(SpdxFormat::Json);
+
+    // The document is converted through a JSON value to write the SPDX 2.3 fields only to SPDX
+    // 2.3 documents.
+    let output = match format {
+        SpdxFormat::Json if options.compact => serde_json::to_string(&spdx_to_value(spdx)?)?,
+        SpdxFormat::Json => serde_json::to_string_pretty(&spdx_to_value(spdx)?)?,
+        SpdxFormat::Yaml => serde_yaml::to_string(&spdx_to_value(spdx)?)?,
+        SpdxFormat::TagValue => spdx_to_tag_value(spdx)?,
+    };
+
+    let mut writer = create_spdx_writer(&output_path)?;
+    writer.write_all(output.as_bytes())?;
+    writer.finish()?;
+
     Ok(())
 }
 
+/// Open the SPDX document at path for reading, or stdin if the path is `-`. Documents ending
+/// with `.gz` or `.zst` are decompressed.
+pub fn open_spdx_reader<P: AsRef<Path>>(path: P) -> anyhow::Result<Box<dyn Read>> {
+    if is_stdio(&path) {
+        return Ok(Box::new(io::stdin()));
+    }
+
+    let file = BufReader::new(File::open(&path)?);
+
+    match compression_extension(&path) {
+        Some("gz") => Ok(Box::new(GzDecoder::new(file))),
+        Some("zst") => Ok(Box::new(zstd::Decoder::with_buffer(file)?)),
+        _ => Ok(Box::new(file)),
+    }
+}
+
+/// Writer of an SPDX document created with [`create_spdx_writer`]. [`SpdxWriter::finish`] must be
+/// called after writing to end the compressed stream and to report errors in flushing the output.
+pub enum SpdxWriter {
+    Stdout(io::Stdout),
+    File(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl SpdxWriter {
+    /// End the compressed stream and flush the output.
+    pub fn finish(self) -> anyhow::Result<()> {
+        match self {
+            SpdxWriter::Stdout(mut stdout) => stdout.flush()?,
+            SpdxWriter::File(mut file) => file.flush()?,
+            SpdxWriter::Gzip(encoder) => encoder.finish()?.flush()?,
+            SpdxWriter::Zstd(encoder) => encoder.finish()?.flush()?,
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for SpdxWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            SpdxWriter::Stdout(stdout) => stdout.write(buf),
+            SpdxWriter::File(file) => file.write(buf),
+            SpdxWriter::Gzip(encoder) => encoder.write(buf),
+            SpdxWriter::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            SpdxWriter::Stdout(stdout) => stdout.flush(),
+            SpdxWriter::File(file) => file.flush(),
+            SpdxWriter::Gzip(encoder) => encoder.flush(),
+            SpdxWriter::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Create the SPDX document at path for writing, or write to stdout if the path is `-`.
+/// Documents ending with `.gz` or `.zst` are compressed.
+pub fn create_spdx_writer<P: AsRef<Path>>(path: P) -> anyhow::Result<SpdxWriter> {
+    if is_stdio(&path) {
+        return Ok(SpdxWriter::Stdout(io::stdout()));
+    }
+
+    let file = BufWriter::new(File::create(&path)?);
+
+    match compression_extension(&path) {
+        Some("gz") => Ok(SpdxWriter::Gzip(GzEncoder::new(
+            file,
+            Compression::default(),
+        ))),
+        Some("zst") => Ok(SpdxWriter::Zstd(zstd::Encoder::new(file, 0)?)),
+        _ => Ok(SpdxWriter::File(file)),
+    }
+}
+
+/// Get the extension of the path if it's a supported compression format.
+fn compression_extension<P: AsRef<Path>>(path: &P) -> Option<&str> {
+    path.as_ref()
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .filter(|extension| ["gz", "zst"].contains(extension))
+}
+
+/// Check if the path ends with a supported compression extension.
+fn is_compressed<P: AsRef<Path>>(path: &P) -> bool {
+    compression_extension(path).is_some()
+}
+
+/// Check if the path is `-`, meaning stdin or stdout.
+fn is_stdio<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref() == Path::new("-")
+}
+
+/// Map SPDX IDs of packages to the files they contain based on the `CONTAINS` relationships of
+/// the [`SPDX`].
+pub fn files_by_package(spdx: &SPDX) -> HashMap<&str, Vec<&FileInformation>> {
+    let files = spdx
+        .file_information
+        .iter()
+        .map(|file| (file.file_spdx_identifier.as_str(), file))
+        .collect::<HashMap<_, _>>();
+
+    let mut files_by_package: HashMap<&str, Vec<&FileInformation>> = HashMap::new();
+
+    for relationship in &spdx.relationships {
+        if relationship.relationship_type != RelationshipType::Contains {
+            continue;
+        }
+
+        if let Some(&file) = files.get(relationship.related_spdx_element.as_str()) {
+            files_by_package
+                .entry(relationship.spdx_element_id.as_str())
+                .or_default()
+                .push(file);
+        }
+    }
+
+    files_by_package
+}
+
+/// License list from the test data, used to avoid fetching the list from GitHub in tests.
+#[cfg(test)]
+pub(crate) fn test_license_list() -> spdx_toolkit::license_list::LicenseList {
+    use spdx_toolkit::license_list::LicenseList;
+
+    let licenses = std::fs::read_to_string("tests/examples/spdx/licenses.json").unwrap();
+    let exceptions = std::fs::read_to_string("tests/examples/spdx/exceptions.json").unwrap();
+
+    let mut license_list: LicenseList = serde_json::from_str(&licenses).unwrap();
+    let exceptions: LicenseList = serde_json::from_str(&exceptions).unwrap();
+    license_list.exceptions = exceptions.exceptions;
+
+    license_list
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_sha256_for_path() {
-        let actual_hash = hash256_for_path("LICENSE").unwrap();
+        let actual_hash = hashes_for_path("LICENSE").unwrap().sha256;
         let expected_hash =
-            String::from("E53CC20D66C471D974F1264DB690B2ED2660816DA7624E75DF744D0D77BA3728");
+            String::from("e53cc20d66c471d974f1264db690b2ed2660816da7624e75df744d0d77ba3728");
         assert_eq!(actual_hash, expected_hash);
     }
+
+    #[test]
+    fn hashes_for_path_are_lowercase() {
+        let hashes = hashes_for_path("LICENSE").unwrap();
+        assert_eq!(hashes.sha1, "1e0664bdf88e875712948dbf3e752cde7931b313");
+        assert_eq!(
+            hashes.sha512,
+            "15ab189676a5eb10dcfe58344d6b853cd3e7f9a6144071311c6a72b109b5310c\
+             838ae225562bc13dba064dbf305fcf9ee13ee7de6fa4e693385acf11112c9c5d"
+        );
+        assert_eq!(hashes.md5, "2e69e93db87614be3f1438d6fc0a3ef8");
+    }
+
+    #[test]
+    fn hashes_from_empty_reader() {
+        let hashes = FileHashes::from_reader(io::empty()).unwrap();
+        assert_eq!(hashes.sha1, "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(hashes.md5, "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(hashes.checksums().len(), 4);
+    }
+
+    #[test]
+    fn malformed_document_reports_the_original_error() {
+        let path = std::env::temp_dir().join("doubleopen_malformed_test.spdx.json");
+        std::fs::write(&path, r#"{"spdxVersion": "SPDX-2.2", "files": 1}"#).unwrap();
+
+        let error = deserialize_spdx(&path, None).unwrap_err();
+        std::fs::remove_file(path).unwrap();
+
+        assert!(error
+            .to_string()
+            .starts_with("Deserializing the SPDX document failed: "));
+    }
+
+    #[test]
+    fn output_format_follows_extension() {
+        let spdx =
+            deserialize_spdx("tests/examples/spdx/SPDXJSONExample-v2.2.spdx.json", None).unwrap();
+        let output_dir = std::env::temp_dir();
+
+        for extension in ["json", "yaml", "spdx", "json.gz", "json.zst"] {
+            let path = output_dir.join(format!("doubleopen_serialize_test.{}", extension));
+            serialize_spdx(&path, &spdx, SpdxOptions::default()).unwrap();
+
+            let serialized = deserialize_spdx(&path, None).unwrap();
+            assert_eq!(serialized.file_information, spdx.file_information);
+
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn sha1_is_of_the_decompressed_document() {
+        let path = "tests/examples/spdx/SPDXJSONExample-v2.2.spdx.json";
+        let compressed = std::env::temp_dir().join(format!(
+            "doubleopen-sha1-{}.spdx.json.gz",
+            std::process::id()
+        ));
+        let mut encoder =
+            GzEncoder::new(File::create(&compressed).unwrap(), Compression::default());
+        io::copy(&mut File::open(path).unwrap(), &mut encoder).unwrap();
+        encoder.finish().unwrap();
+
+        let (_, sha1) = deserialize_spdx_with_sha1(&compressed, None).unwrap();
+        std::fs::remove_file(&compressed).unwrap();
+
+        assert_eq!(sha1, hashes_for_path(path).unwrap().sha1);
+    }
 }